@@ -0,0 +1,307 @@
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::audio::{play_sfx, Sfx};
+use crate::context::Context;
+use crate::level::pack::Pack;
+use crate::save::Save;
+
+/// where the achievements are declared, in the assets dir
+const ACHIEVEMENTS_FILE: &str = "achievements.toml";
+
+/// a goal for the player, defined in `assets/achievements.toml`
+#[derive(Debug, Deserialize, Clone)]
+pub struct Achievement {
+    /// id used for save data; NOTE: changing this will break saves, don't change
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+/// what needs to happen for an achievement to unlock
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum Condition {
+    /// complete this many different levels across all packs
+    LevelsCompleted { count: usize },
+    /// complete every level in the pack with the slug, or any pack if not specified
+    PackCompleted { pack: Option<String> },
+    /// complete a level without rewinding
+    NoRewind,
+    /// complete the level in this many pushes or fewer
+    LevelPushes {
+        pack: String,
+        level: String,
+        pushes: i32,
+    },
+    /// complete a level that has a par for pushes in that many pushes or fewer, in the pack with
+    /// the slug, or any pack if not specified
+    ParPushes { pack: Option<String> },
+    /// take this many steps in total
+    TotalSteps { count: u64 },
+    /// push crates this many times in total
+    TotalPushes { count: u64 },
+}
+
+/// something that happened while playing that could unlock an achievement
+pub enum Event<'a> {
+    /// the player took a step
+    Step,
+    LevelCompleted {
+        pack: &'a Pack,
+        level_title: &'a str,
+        steps: i32,
+        pushes: i32,
+        /// whether or not any moves were rewound since the level was started or reset
+        rewound: bool,
+    },
+}
+
+#[derive(Deserialize)]
+struct AchievementsFile {
+    achievements: Vec<Achievement>,
+}
+
+/// every achievement that can be unlocked
+pub struct AchievementList {
+    pub achievements: Vec<Achievement>,
+}
+
+impl AchievementList {
+    pub async fn new(base_assets_path: &Path) -> Self {
+        let toml_str = macroquad::file::load_string(
+            base_assets_path.join(ACHIEVEMENTS_FILE).to_str().unwrap(),
+        )
+        .await
+        .unwrap();
+        Self::parse(&toml_str)
+    }
+
+    /// like `new`, but reads the file right away instead of waiting on an async load, for use
+    /// outside of the game loop
+    #[cfg(not(target_family = "wasm"))]
+    pub fn read(base_assets_path: &Path) -> Self {
+        let toml_str = std::fs::read_to_string(base_assets_path.join(ACHIEVEMENTS_FILE)).unwrap();
+        Self::parse(&toml_str)
+    }
+
+    /// checks the event against every locked achievement, unlocking the ones that are now met
+    /// and returning them
+    pub fn unlock_met(&self, save: &mut Save, event: &Event) -> Vec<&Achievement> {
+        let met: Vec<&Achievement> = self
+            .achievements
+            .iter()
+            .filter(|a| !save.is_achievement_unlocked(&a.id) && a.condition.is_met(save, event))
+            .collect();
+        for achievement in &met {
+            save.unlock_achievement(&achievement.id);
+        }
+        met
+    }
+
+    fn parse(toml_str: &str) -> Self {
+        let file: AchievementsFile = toml::from_str(toml_str).unwrap();
+        Self {
+            achievements: file.achievements,
+        }
+    }
+}
+
+impl Condition {
+    pub fn is_met(&self, save: &Save, event: &Event) -> bool {
+        match (self, event) {
+            (Condition::LevelsCompleted { count }, _) => save.completed_level_count() >= *count,
+            (Condition::TotalSteps { count }, _) => save.stats().steps >= *count,
+            (Condition::TotalPushes { count }, _) => save.stats().pushes >= *count,
+            (Condition::PackCompleted { pack: slug }, Event::LevelCompleted { pack, .. }) => {
+                slug.as_ref().is_none_or(|s| *s == pack.slug)
+                    && pack
+                        .levels
+                        .iter()
+                        .all(|l| save.is_level_complete(&pack.slug, &l.title))
+            }
+            (Condition::NoRewind, Event::LevelCompleted { rewound, .. }) => !rewound,
+            (
+                Condition::LevelPushes {
+                    pack: slug,
+                    level,
+                    pushes: max_pushes,
+                },
+                Event::LevelCompleted {
+                    pack,
+                    level_title,
+                    pushes,
+                    ..
+                },
+            ) => *slug == pack.slug && level == level_title && pushes <= max_pushes,
+            (
+                Condition::ParPushes { pack: slug },
+                Event::LevelCompleted {
+                    pack,
+                    level_title,
+                    pushes,
+                    ..
+                },
+            ) => {
+                slug.as_ref().is_none_or(|s| *s == pack.slug)
+                    && pack
+                        .levels
+                        .iter()
+                        .find(|l| l.title == *level_title)
+                        .and_then(|l| l.par_pushes)
+                        .is_some_and(|par| *pushes <= par)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// unlocks and announces the achievements that the event meets
+pub fn handle_event(ctx: &mut Context, event: Event) {
+    let unlocked: Vec<String> = ctx
+        .achievements
+        .unlock_met(&mut ctx.save, &event)
+        .iter()
+        .map(|a| a.title.clone())
+        .collect();
+
+    for title in unlocked {
+        ctx.toasts.push(format!("Achievement unlocked: {}", title));
+        play_sfx(ctx, Sfx::MenuSelect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::pack::{Difficulty, PackLevel, Progression};
+
+    fn pack() -> Pack {
+        Pack {
+            title: "Test Pack".to_string(),
+            slug: "test".to_string(),
+            description: String::new(),
+            author: String::new(),
+            license: String::new(),
+            year: 2024,
+            levels: vec![PackLevel {
+                title: "Level 1".to_string(),
+                data: String::new(),
+                par_moves: None,
+                par_pushes: None,
+            }],
+            version: "0.1".to_string(),
+            file: None,
+            difficulty: Difficulty::Easy,
+            order: 0,
+            progression: Progression::Open,
+            requires: None,
+        }
+    }
+
+    fn completed<'a>(pack: &'a Pack, pushes: i32, rewound: bool) -> Event<'a> {
+        Event::LevelCompleted {
+            pack,
+            level_title: "Level 1",
+            steps: 10,
+            pushes,
+            rewound,
+        }
+    }
+
+    #[test]
+    fn test_bundled_achievements_parse() {
+        let toml_str = std::fs::read_to_string("./assets/achievements.toml").unwrap();
+        let list = AchievementList::parse(&toml_str);
+        assert!(!list.achievements.is_empty());
+    }
+
+    #[test]
+    fn test_pack_completed() {
+        let pack = pack();
+        let mut save = Save::default();
+        let condition = Condition::PackCompleted { pack: None };
+        assert!(!condition.is_met(&save, &completed(&pack, 1, false)));
+
+        save.complete_level("test".to_string(), "Level 1".to_string(), 10, 1, None);
+        assert!(condition.is_met(&save, &completed(&pack, 1, false)));
+        assert!(!condition.is_met(&save, &Event::Step));
+
+        let other_pack = Condition::PackCompleted {
+            pack: Some("other".to_string()),
+        };
+        assert!(!other_pack.is_met(&save, &completed(&pack, 1, false)));
+    }
+
+    #[test]
+    fn test_level_pushes_and_no_rewind() {
+        let pack = pack();
+        let save = Save::default();
+        let condition = Condition::LevelPushes {
+            pack: "test".to_string(),
+            level: "Level 1".to_string(),
+            pushes: 3,
+        };
+        assert!(condition.is_met(&save, &completed(&pack, 3, true)));
+        assert!(!condition.is_met(&save, &completed(&pack, 4, true)));
+
+        assert!(Condition::NoRewind.is_met(&save, &completed(&pack, 4, false)));
+        assert!(!Condition::NoRewind.is_met(&save, &completed(&pack, 4, true)));
+    }
+
+    #[test]
+    fn test_par_pushes() {
+        let mut pack = pack();
+        let save = Save::default();
+        let condition = Condition::ParPushes { pack: None };
+        // no par to compare with
+        assert!(!condition.is_met(&save, &completed(&pack, 1, false)));
+
+        pack.levels[0].par_pushes = Some(3);
+        assert!(condition.is_met(&save, &completed(&pack, 3, false)));
+        assert!(!condition.is_met(&save, &completed(&pack, 4, false)));
+
+        let in_pack = |slug: &str| Condition::ParPushes {
+            pack: Some(slug.to_string()),
+        };
+        assert!(in_pack("test").is_met(&save, &completed(&pack, 2, false)));
+        assert!(!in_pack("other").is_met(&save, &completed(&pack, 2, false)));
+    }
+
+    #[test]
+    fn test_unlock_met() {
+        let list = AchievementList::parse(
+            r#"
+            [[achievements]]
+            id = "no-rewind"
+            title = "No Take-Backs"
+            description = ""
+            condition = { type = "NoRewind" }
+            "#,
+        );
+        let pack = pack();
+        let mut save = Save::default();
+        assert!(list
+            .unlock_met(&mut save, &completed(&pack, 1, true))
+            .is_empty());
+
+        let unlocked = list.unlock_met(&mut save, &completed(&pack, 1, false));
+        assert_eq!(unlocked.len(), 1);
+        assert!(save.is_achievement_unlocked("no-rewind"));
+        // already unlocked, so it isn't announced again
+        assert!(list
+            .unlock_met(&mut save, &completed(&pack, 1, false))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_totals() {
+        let mut save = Save::default();
+        let condition = Condition::TotalSteps { count: 2 };
+        save.record_step(true);
+        assert!(!condition.is_met(&save, &Event::Step));
+        save.record_step(false);
+        assert!(condition.is_met(&save, &Event::Step));
+    }
+}
@@ -22,15 +22,11 @@ pub const ASSETS_DIR: &str = "assets";
 #[cfg(not(target_family = "wasm"))]
 pub fn determine_asset_path() -> PathBuf {
     // try to find assets from the `--assets` CLI arg
-    let parsed_args = parse_args(env::args().collect());
-    if let Some(arg) = parsed_args.iter().find(|arg| arg.key == "assets") {
-        let mut cargo_path = PathBuf::new();
-        cargo_path.push(&arg.value);
-        return cargo_path;
+    if let Some(assets) = &crate::cli::args().assets {
+        return assets.clone();
     }
 
     // try to find assets dir in the SOKOWORLD_ASSETS dir
-    use std::env;
     match std::env::var("SOKOWORLD_ASSETS") {
         Ok(sokoworld_assets_dir) => {
             let mut cargo_path = PathBuf::new();
@@ -90,88 +86,3 @@ pub fn determine_asset_path() -> PathBuf {
 pub fn determine_asset_path() -> PathBuf {
     PathBuf::from(r"assets")
 }
-
-#[cfg(not(target_family = "wasm"))]
-#[derive(PartialEq, Debug)]
-struct Arg {
-    key: String,
-    value: String,
-}
-
-#[cfg(not(target_family = "wasm"))]
-/// Simple CLI arg parser for specifying values with `--foo bar` or `--foo=bar`; does not support
-/// Boolean values
-fn parse_args(args: Vec<String>) -> Vec<Arg> {
-    let mut parsed_args: Vec<Arg> = vec![];
-    for (i, arg) in args.iter().enumerate() {
-        if arg.contains("=") {
-            if arg.starts_with("-") {
-                let parts: Vec<&str> = arg.split("=").collect();
-                let key = parts[0].to_string().replace("--", "");
-                parsed_args.push(Arg {
-                    key: key.to_owned(),
-                    value: parts[1].to_string(),
-                });
-            }
-        } else if arg.starts_with("-") {
-            let key = arg.replace("--", "");
-            parsed_args.push(Arg {
-                key: key.to_owned(),
-                value: args[i + 1].to_string(),
-            });
-        }
-    }
-    parsed_args
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_parses_equal_args() {
-        let args = parse_args(vec![String::from("--foo=bar")]);
-        assert_eq!(
-            args,
-            vec![Arg {
-                key: String::from("foo"),
-                value: String::from("bar")
-            }]
-        );
-    }
-
-    #[test]
-    fn it_parses_space_args() {
-        let args = parse_args(vec![String::from("--foo"), String::from("bar")]);
-        assert_eq!(
-            args,
-            vec![Arg {
-                key: String::from("foo"),
-                value: String::from("bar")
-            }]
-        );
-    }
-
-    #[test]
-    fn it_parses_mixed_args() {
-        let args = parse_args(vec![
-            String::from("--foo=bar"),
-            String::from("--name"),
-            String::from("brett"),
-        ]);
-
-        assert_eq!(
-            args,
-            vec![
-                Arg {
-                    key: String::from("foo"),
-                    value: String::from("bar")
-                },
-                Arg {
-                    key: String::from("name"),
-                    value: String::from("brett")
-                }
-            ]
-        )
-    }
-}
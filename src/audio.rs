@@ -1,109 +1,315 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 
-use macroquad::audio::Sound;
+use macroquad::audio::{PlaySoundParams, Sound};
+use macroquad::logging::warn;
+use serde::Deserialize;
 
 use crate::context::Context;
+use music::Music;
 
-pub struct SfxAtlas {
-    pub push: Sound,
-    pub crate_on_storage_location: Sound,
-    pub level_complete: Sound,
-    pub reset: Sound,
-    pub footstep: Sound,
-    pub menu_cancel: Sound,
-    pub menu_select: Sound,
-    pub menu_move: Sound,
-    pub cant_move: Sound,
+pub mod music;
+
+/// where the sound effects are declared, in the assets dir
+pub const SFX_MANIFEST_FILE: &str = "sfx.toml";
+
+/// how many pitches a sound with pitch variation is loaded at, spread evenly across the range
+const PITCH_VARIANTS: usize = 5;
+
+/// a group of sounds whose volume is set together, with the master bus over all the others
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioBus {
+    Master,
+    Sfx,
+    Music,
+}
+
+impl AudioBus {
+    pub const ALL: [AudioBus; 3] = [AudioBus::Master, AudioBus::Sfx, AudioBus::Music];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioBus::Master => "Master",
+            AudioBus::Sfx => "Sound Effects",
+            AudioBus::Music => "Music",
+        }
+    }
+}
+
+/// something that happens in the game that plays a sound, named in the manifest
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sfx {
+    Push,
+    CrateOnStorageLocation,
+    LevelComplete,
+    Reset,
+    Footstep,
+    MenuCancel,
+    MenuSelect,
+    MenuMove,
+    CantMove,
+}
+
+impl Sfx {
+    pub const ALL: [Sfx; 9] = [
+        Sfx::Push,
+        Sfx::CrateOnStorageLocation,
+        Sfx::LevelComplete,
+        Sfx::Reset,
+        Sfx::Footstep,
+        Sfx::MenuCancel,
+        Sfx::MenuSelect,
+        Sfx::MenuMove,
+        Sfx::CantMove,
+    ];
+
+    /// the key for the sound in the manifest
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sfx::Push => "push",
+            Sfx::CrateOnStorageLocation => "crate_on_storage_location",
+            Sfx::LevelComplete => "level_complete",
+            Sfx::Reset => "reset",
+            Sfx::Footstep => "footstep",
+            Sfx::MenuCancel => "menu_cancel",
+            Sfx::MenuSelect => "menu_select",
+            Sfx::MenuMove => "menu_move",
+            Sfx::CantMove => "cant_move",
+        }
+    }
+}
+
+/// the files for each sound effect, so a different set of sounds can be used by changing it
+#[derive(Debug, Default, Deserialize)]
+pub struct SfxManifest {
+    #[serde(default)]
+    pub sounds: BTreeMap<String, SoundEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SoundEntry {
+    /// paths to the sound files from the assets dir, one of which is picked each time it plays
+    pub files: Vec<String>,
+    /// how loud it is, from 0 to 1, before the sound effect volume
+    #[serde(default = "full_volume")]
+    pub volume: f32,
+    /// how far the pitch can randomly go up or down, like 0.1 for up to 10%; only works for WAV
+    /// files
+    #[serde(default)]
+    pub pitch_variation: f32,
+}
+
+fn full_volume() -> f32 {
+    1.
+}
+
+impl SfxManifest {
+    pub fn parse(toml_str: &str) -> Result<Self, String> {
+        toml::from_str(toml_str).map_err(|e| e.to_string())
+    }
 }
+
+/// the pitches to load a sound at for the variation, from lowest to highest
+pub fn pitches(variation: f32) -> Vec<f32> {
+    let variation = variation.clamp(0., 0.9);
+    if variation == 0. {
+        return vec![1.];
+    }
+    (0..PITCH_VARIANTS)
+        .map(|i| 1. - variation + 2. * variation * i as f32 / (PITCH_VARIANTS - 1) as f32)
+        .collect()
+}
+
+/// the WAV file played at a different pitch, by changing the sample rate in its header so it's
+/// played faster or slower; none when it isn't a WAV file
+pub fn wav_with_pitch(wav: &[u8], pitch: f32) -> Option<Vec<u8>> {
+    if wav.len() < 12 || &wav[0..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return None;
+    }
+
+    // the format chunk is usually first, but other chunks are allowed before it
+    let mut offset = 12;
+    while offset + 8 <= wav.len() {
+        let id = &wav[offset..offset + 4];
+        let size = u32::from_le_bytes(wav[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let data = offset + 8;
+        if id == b"fmt " {
+            if data + 14 > wav.len() {
+                return None;
+            }
+            let read_u32 = |at: usize| u32::from_le_bytes(wav[at..at + 4].try_into().unwrap());
+            let sample_rate = (read_u32(data + 4) as f32 * pitch).round() as u32;
+            let block_align = u16::from_le_bytes(wav[data + 12..data + 14].try_into().unwrap());
+
+            let mut pitched = wav.to_vec();
+            pitched[data + 4..data + 8].copy_from_slice(&sample_rate.to_le_bytes());
+            pitched[data + 8..data + 12]
+                .copy_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+            return Some(pitched);
+        }
+        // chunks are padded to an even size
+        offset = data + size + size % 2;
+    }
+    None
+}
+
+/// the sounds for a sound effect, with one picked at random each time it plays
+pub struct SoundEffect {
+    variants: Vec<Sound>,
+    volume: f32,
+}
+
+impl SoundEffect {
+    /// loads every file at every pitch, leaving out the ones that can't be loaded
+    async fn load(base_assets_path: &Path, name: &str, entry: &SoundEntry) -> Option<Self> {
+        let mut variants = vec![];
+        for file in &entry.files {
+            let path = base_assets_path.join(file);
+            let bytes = match macroquad::file::load_file(path.to_str().unwrap()).await {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    warn!("couldn't load {} for the {} sound: {}", file, name, err);
+                    continue;
+                }
+            };
+
+            let is_wav = wav_with_pitch(&bytes, 1.).is_some();
+            if !is_wav && entry.pitch_variation > 0. {
+                warn!("{} isn't a WAV file, so its pitch can't vary", file);
+            }
+            let pitches = if is_wav {
+                pitches(entry.pitch_variation)
+            } else {
+                vec![1.]
+            };
+            for pitch in pitches {
+                let pitched = wav_with_pitch(&bytes, pitch).unwrap_or_else(|| bytes.clone());
+                match macroquad::audio::load_sound_from_bytes(&pitched).await {
+                    Ok(sound) => variants.push(sound),
+                    Err(err) => warn!("couldn't load {} for the {} sound: {}", file, name, err),
+                }
+            }
+        }
+
+        if variants.is_empty() {
+            return None;
+        }
+        Some(Self {
+            variants,
+            volume: entry.volume.clamp(0., 1.),
+        })
+    }
+}
+
 pub struct AudioAtlas {
-    pub sfx: SfxAtlas,
+    /// the sound for each sound effect by name, leaving out the ones that couldn't be loaded
+    pub sfx: BTreeMap<String, SoundEffect>,
+    pub music: Music,
 }
 
 impl AudioAtlas {
     pub async fn new(base_assets_path: &Path) -> Self {
+        let manifest_path = base_assets_path.join(SFX_MANIFEST_FILE);
+        let manifest = match macroquad::file::load_string(manifest_path.to_str().unwrap()).await {
+            Ok(toml_str) => SfxManifest::parse(&toml_str).unwrap_or_else(|err| {
+                warn!(
+                    "{} is invalid, so there are no sounds: {}",
+                    SFX_MANIFEST_FILE, err
+                );
+                SfxManifest::default()
+            }),
+            Err(err) => {
+                warn!(
+                    "couldn't load {}, so there are no sounds: {}",
+                    SFX_MANIFEST_FILE, err
+                );
+                SfxManifest::default()
+            }
+        };
+
+        let mut sfx = BTreeMap::new();
+        for (name, entry) in &manifest.sounds {
+            if let Some(effect) = SoundEffect::load(base_assets_path, name, entry).await {
+                sfx.insert(name.clone(), effect);
+            }
+        }
+        for effect in Sfx::ALL {
+            if !manifest.sounds.contains_key(effect.name()) {
+                warn!("{} has no {} sound", SFX_MANIFEST_FILE, effect.name());
+            }
+        }
+
         Self {
-            sfx: SfxAtlas {
-                push: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/melos/dialogBlip2.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-                level_complete: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/melos/get_item.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-                reset: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/melos/save.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-                footstep: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/melos/footstep_4.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-                menu_cancel: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/melos/menuCancel.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-                menu_select: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/melos/menuSelect.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-                menu_move: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/melos/menuMove.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-                crate_on_storage_location: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/melos/pieceSelect.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-                cant_move: macroquad::audio::load_sound(
-                    base_assets_path
-                        .join("sfx/melos/pieceCantPlace.wav")
-                        .to_str()
-                        .unwrap(),
-                )
-                .await
-                .unwrap(),
-            },
+            sfx,
+            music: Music::new(base_assets_path).await,
         }
     }
 }
 
-pub fn play_sfx(ctx: &Context, sfx: &Sound) {
-    if ctx.settings.is_muted() {
+/// plays one of the sound effect's sounds once at the sound effect volume; sound effects that
+/// couldn't be loaded are silent
+pub fn play_sfx(ctx: &Context, sfx: Sfx) {
+    let Some(effect) = ctx.audio.sfx.get(sfx.name()) else {
         return;
+    };
+    let volume = ctx.settings.output_volume(AudioBus::Sfx) * effect.volume;
+    if volume <= 0. {
+        return;
+    }
+
+    let variant = macroquad::rand::gen_range(0, effect.variants.len());
+    macroquad::audio::play_sound(
+        &effect.variants[variant],
+        PlaySoundParams {
+            looped: false,
+            volume,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the start of a 44.1 kHz stereo 16-bit WAV file, with a chunk before the format chunk
+    fn wav() -> Vec<u8> {
+        let mut wav = b"RIFF\0\0\0\0WAVE".to_vec();
+        wav.extend(b"LIST\x03\0\0\0abc\0");
+        wav.extend(b"fmt \x10\0\0\0\x01\0\x02\0");
+        wav.extend(44100u32.to_le_bytes());
+        wav.extend((44100u32 * 4).to_le_bytes());
+        wav.extend(b"\x04\0\x10\0data\0\0\0\0");
+        wav
+    }
+
+    fn read_u32(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
     }
 
-    macroquad::audio::play_sound_once(sfx);
+    #[test]
+    fn test_wav_with_pitch() {
+        let pitched = wav_with_pitch(&wav(), 1.5).unwrap();
+        // the sample rate and byte rate after the padded chunk and the format chunk's header
+        assert_eq!(read_u32(&pitched, 36), 66150);
+        assert_eq!(read_u32(&pitched, 40), 66150 * 4);
+        assert_eq!(pitched.len(), wav().len());
+
+        assert_eq!(wav_with_pitch(b"OggS\0\0\0\0\0\0\0\0\0\0", 1.5), None);
+        assert_eq!(wav_with_pitch(&wav()[..30], 1.5), None);
+    }
+
+    #[test]
+    fn test_pitches() {
+        assert_eq!(pitches(0.), vec![1.]);
+        assert_eq!(pitches(0.25), vec![0.75, 0.875, 1., 1.125, 1.25]);
+    }
+
+    #[test]
+    fn test_bundled_manifest_has_every_sound() {
+        let manifest = SfxManifest::parse(include_str!("../assets/sfx.toml")).unwrap();
+        for sfx in Sfx::ALL {
+            let entry = &manifest.sounds[sfx.name()];
+            assert!(!entry.files.is_empty(), "{}", sfx.name());
+        }
+    }
 }
@@ -0,0 +1,427 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use macroquad::audio::{PlaySoundParams, Sound};
+use macroquad::logging::warn;
+use serde::Deserialize;
+
+/// where the playlists are declared, in the assets dir
+pub const MUSIC_MANIFEST_FILE: &str = "music.toml";
+
+/// played on the main menu and the scenes opened from it
+pub const MENU_PLAYLIST: &str = "menu";
+
+/// played while in a level, unless the pack has a playlist of its own
+pub const GAMEPLAY_PLAYLIST: &str = "gameplay";
+
+/// seconds for one playlist to fade out while the next one fades in
+const CROSSFADE_SECONDS: f32 = 1.5;
+
+/// the playlists of music tracks, and which packs play which playlist
+#[derive(Debug, Default, Deserialize)]
+pub struct MusicManifest {
+    #[serde(default)]
+    pub playlists: BTreeMap<String, Vec<TrackEntry>>,
+    /// the playlist for each pack by slug, played instead of the gameplay one
+    #[serde(default)]
+    pub packs: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrackEntry {
+    /// path to the sound file from the assets dir
+    pub file: String,
+    /// seconds before moving on to the next track in the playlist; without it the track loops
+    /// until the playlist changes
+    pub length: Option<f32>,
+}
+
+impl MusicManifest {
+    pub fn parse(toml_str: &str) -> Result<Self, String> {
+        toml::from_str(toml_str).map_err(|e| e.to_string())
+    }
+}
+
+/// a track in one of the playlists
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackId {
+    pub playlist: String,
+    pub index: usize,
+}
+
+/// what the jukebox wants done with the sounds this frame
+#[derive(Clone, Debug, PartialEq)]
+pub enum MusicCommand {
+    Play {
+        track: TrackId,
+        looped: bool,
+        volume: f32,
+    },
+    SetVolume {
+        track: TrackId,
+        volume: f32,
+    },
+    Stop {
+        track: TrackId,
+    },
+}
+
+/// a playlist being played, either the current one or one fading out after it was switched from
+#[derive(Debug)]
+struct Channel {
+    playlist: String,
+    index: usize,
+    /// seconds into the track
+    position: f32,
+    /// how far it has faded in, from 0 to 1
+    fade: f32,
+    fading_in: bool,
+}
+
+impl Channel {
+    fn track(&self) -> TrackId {
+        TrackId {
+            playlist: self.playlist.clone(),
+            index: self.index,
+        }
+    }
+}
+
+/// decides which tracks play and how loud, working out when to move on to the next track and how
+/// to crossfade between playlists, without touching any sounds
+#[derive(Debug, Default)]
+pub struct Jukebox {
+    /// the length of each track in each playlist
+    lengths: BTreeMap<String, Vec<Option<f32>>>,
+    /// the playlists being played, with the one fading in last
+    channels: Vec<Channel>,
+}
+
+impl Jukebox {
+    pub fn new(lengths: BTreeMap<String, Vec<Option<f32>>>) -> Self {
+        Self {
+            lengths,
+            channels: vec![],
+        }
+    }
+
+    /// the playlist fading in or playing, if any
+    pub fn current(&self) -> Option<&str> {
+        self.channels
+            .last()
+            .filter(|c| c.fading_in)
+            .map(|c| c.playlist.as_str())
+    }
+
+    /// moves the music along by a frame, crossfading to the playlist if it isn't the current
+    /// one; none keeps the current one playing, and a playlist with no tracks fades to silence
+    pub fn update(&mut self, dt: f32, playlist: Option<&str>, volume: f32) -> Vec<MusicCommand> {
+        let mut commands = vec![];
+        if let Some(playlist) = playlist {
+            if self.current() != Some(playlist) {
+                self.switch_to(playlist, &mut commands);
+            }
+        }
+
+        let fade_step = dt / CROSSFADE_SECONDS;
+        for channel in &mut self.channels {
+            channel.fade = if channel.fading_in {
+                (channel.fade + fade_step).min(1.)
+            } else {
+                (channel.fade - fade_step).max(0.)
+            };
+
+            let lengths = &self.lengths[&channel.playlist];
+            channel.position += dt;
+            if let Some(length) = lengths[channel.index] {
+                if channel.position >= length {
+                    commands.push(MusicCommand::Stop {
+                        track: channel.track(),
+                    });
+                    channel.index = (channel.index + 1) % lengths.len();
+                    channel.position -= length;
+                    commands.push(MusicCommand::Play {
+                        track: channel.track(),
+                        looped: lengths[channel.index].is_none(),
+                        volume: volume * channel.fade,
+                    });
+                }
+            }
+
+            commands.push(MusicCommand::SetVolume {
+                track: channel.track(),
+                volume: volume * channel.fade,
+            });
+        }
+
+        self.channels.retain(|channel| {
+            let silent = !channel.fading_in && channel.fade <= 0.;
+            if silent {
+                commands.push(MusicCommand::Stop {
+                    track: channel.track(),
+                });
+            }
+            !silent
+        });
+
+        commands
+    }
+
+    fn switch_to(&mut self, playlist: &str, commands: &mut Vec<MusicCommand>) {
+        for channel in &mut self.channels {
+            channel.fading_in = false;
+        }
+
+        let Some(lengths) = self.lengths.get(playlist).filter(|l| !l.is_empty()) else {
+            return;
+        };
+        // switching back to a playlist that's still fading out picks up where it was
+        if let Some(i) = self.channels.iter().position(|c| c.playlist == playlist) {
+            let mut channel = self.channels.remove(i);
+            channel.fading_in = true;
+            self.channels.push(channel);
+            return;
+        }
+
+        let channel = Channel {
+            playlist: playlist.to_string(),
+            index: 0,
+            position: 0.,
+            fade: 0.,
+            fading_in: true,
+        };
+        commands.push(MusicCommand::Play {
+            track: channel.track(),
+            looped: lengths[0].is_none(),
+            volume: 0.,
+        });
+        self.channels.push(channel);
+    }
+}
+
+/// the music from the manifest, played by playlist
+pub struct Music {
+    jukebox: Jukebox,
+    sounds: BTreeMap<String, Vec<Sound>>,
+    packs: BTreeMap<String, String>,
+}
+
+impl Music {
+    /// loads the tracks in the manifest, leaving out the ones that can't be loaded so a missing
+    /// file doesn't stop the game; without a manifest there's no music
+    pub async fn new(base_assets_path: &Path) -> Self {
+        let manifest_path = base_assets_path.join(MUSIC_MANIFEST_FILE);
+        let manifest = match macroquad::file::load_string(manifest_path.to_str().unwrap()).await {
+            Ok(toml_str) => MusicManifest::parse(&toml_str).unwrap_or_else(|err| {
+                warn!(
+                    "{} is invalid, so there's no music: {}",
+                    MUSIC_MANIFEST_FILE, err
+                );
+                MusicManifest::default()
+            }),
+            Err(_) => MusicManifest::default(),
+        };
+
+        let mut lengths = BTreeMap::new();
+        let mut sounds = BTreeMap::new();
+        for (playlist, tracks) in manifest.playlists {
+            let mut playlist_lengths = vec![];
+            let mut playlist_sounds = vec![];
+            for track in tracks {
+                let path = base_assets_path.join(&track.file);
+                match macroquad::audio::load_sound(path.to_str().unwrap()).await {
+                    Ok(sound) => {
+                        playlist_lengths.push(track.length);
+                        playlist_sounds.push(sound);
+                    }
+                    Err(err) => warn!(
+                        "couldn't load {} from the {} playlist: {}",
+                        track.file, playlist, err
+                    ),
+                }
+            }
+            lengths.insert(playlist.clone(), playlist_lengths);
+            sounds.insert(playlist, playlist_sounds);
+        }
+
+        Self {
+            jukebox: Jukebox::new(lengths),
+            sounds,
+            packs: manifest.packs,
+        }
+    }
+
+    /// the playlist to play while in a level of the pack
+    pub fn playlist_for_pack(&self, slug: &str) -> &str {
+        self.packs
+            .get(slug)
+            .map_or(GAMEPLAY_PLAYLIST, |playlist| playlist.as_str())
+    }
+
+    /// plays the playlist, crossfading from the one before, at the music volume
+    pub fn update(&mut self, dt: f32, playlist: Option<&str>, volume: f32) {
+        for command in self.jukebox.update(dt, playlist, volume) {
+            match command {
+                MusicCommand::Play {
+                    track,
+                    looped,
+                    volume,
+                } => macroquad::audio::play_sound(
+                    self.sound(&track),
+                    PlaySoundParams { looped, volume },
+                ),
+                MusicCommand::SetVolume { track, volume } => {
+                    macroquad::audio::set_sound_volume(self.sound(&track), volume)
+                }
+                MusicCommand::Stop { track } => macroquad::audio::stop_sound(self.sound(&track)),
+            }
+        }
+    }
+
+    fn sound(&self, track: &TrackId) -> &Sound {
+        &self.sounds[&track.playlist][track.index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jukebox() -> Jukebox {
+        Jukebox::new(BTreeMap::from([
+            ("menu".to_string(), vec![None]),
+            ("gameplay".to_string(), vec![Some(2.), Some(1.)]),
+            ("empty".to_string(), vec![]),
+        ]))
+    }
+
+    fn track(playlist: &str, index: usize) -> TrackId {
+        TrackId {
+            playlist: playlist.to_string(),
+            index,
+        }
+    }
+
+    /// the volume each track was set to, in the order they're played
+    fn volumes(commands: &[MusicCommand]) -> Vec<(TrackId, f32)> {
+        commands
+            .iter()
+            .filter_map(|c| match c {
+                MusicCommand::SetVolume { track, volume } => Some((track.clone(), *volume)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = MusicManifest::parse(
+            r#"
+            [[playlists.menu]]
+            file = "music/title.ogg"
+
+            [[playlists.puzzles]]
+            file = "music/a.ogg"
+            length = 90.5
+
+            [packs]
+            pack-a = "puzzles"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.playlists["menu"][0].length, None);
+        assert_eq!(manifest.playlists["puzzles"][0].length, Some(90.5));
+        assert_eq!(manifest.packs["pack-a"], "puzzles");
+
+        assert!(MusicManifest::parse("[[playlists.menu]]\nlength = 1.0\n").is_err());
+    }
+
+    #[test]
+    fn test_bundled_manifest_parses() {
+        MusicManifest::parse(include_str!("../../assets/music.toml")).unwrap();
+    }
+
+    #[test]
+    fn test_crossfade() {
+        let mut jukebox = jukebox();
+        let commands = jukebox.update(0.75, Some("menu"), 1.);
+        assert_eq!(
+            commands[0],
+            MusicCommand::Play {
+                track: track("menu", 0),
+                looped: true,
+                volume: 0.
+            }
+        );
+        assert_eq!(volumes(&commands), vec![(track("menu", 0), 0.5)]);
+
+        // none keeps playing what's there
+        let commands = jukebox.update(0.75, None, 0.5);
+        assert_eq!(volumes(&commands), vec![(track("menu", 0), 0.5)]);
+
+        let commands = jukebox.update(0.75, Some("gameplay"), 1.);
+        assert_eq!(
+            volumes(&commands),
+            vec![(track("menu", 0), 0.5), (track("gameplay", 0), 0.5)]
+        );
+        let commands = jukebox.update(0.75, Some("gameplay"), 1.);
+        assert_eq!(
+            volumes(&commands),
+            vec![(track("menu", 0), 0.), (track("gameplay", 0), 1.)]
+        );
+        assert!(commands.contains(&MusicCommand::Stop {
+            track: track("menu", 0)
+        }));
+        assert_eq!(jukebox.current(), Some("gameplay"));
+    }
+
+    #[test]
+    fn test_switching_back_while_fading_out() {
+        let mut jukebox = jukebox();
+        jukebox.update(1.5, Some("menu"), 1.);
+        jukebox.update(0.75, Some("gameplay"), 1.);
+
+        // the menu music fades back in from where it was instead of starting over
+        let commands = jukebox.update(0.375, Some("menu"), 1.);
+        assert!(!commands
+            .iter()
+            .any(|c| matches!(c, MusicCommand::Play { .. })));
+        assert_eq!(
+            volumes(&commands),
+            vec![(track("gameplay", 0), 0.25), (track("menu", 0), 0.75)]
+        );
+    }
+
+    #[test]
+    fn test_playlist_moves_on_and_loops() {
+        let mut jukebox = jukebox();
+        jukebox.update(1.5, Some("gameplay"), 1.);
+        let commands = jukebox.update(0.5, None, 1.);
+        assert_eq!(
+            &commands[..2],
+            [
+                MusicCommand::Stop {
+                    track: track("gameplay", 0)
+                },
+                MusicCommand::Play {
+                    track: track("gameplay", 1),
+                    looped: false,
+                    volume: 1.
+                }
+            ]
+        );
+
+        // after the last track it goes back to the first
+        let commands = jukebox.update(1., None, 1.);
+        assert_eq!(volumes(&commands), vec![(track("gameplay", 0), 1.)]);
+    }
+
+    #[test]
+    fn test_missing_playlist_fades_out() {
+        let mut jukebox = jukebox();
+        jukebox.update(1.5, Some("menu"), 1.);
+        jukebox.update(1.5, Some("empty"), 1.);
+        assert_eq!(jukebox.current(), None);
+        assert!(jukebox.update(0.1, Some("nothing"), 1.).is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+//! checks pack files for problems before they're shipped, see `--help`
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use sokoworld::level::pack::pack_files_in_dir;
+use sokoworld::level::solver::DEFAULT_MAX_STATES;
+use sokoworld::lint::{lint_packs, Diagnostic, Severity};
+
+fn usage() -> String {
+    format!(
+        "Checks SokoWorld pack files for problems
+
+Usage: sokoworld-lint [OPTIONS] <PACK>...
+
+Each PACK is a pack TOML file or a dir of them.
+
+Options:
+  --json              print the results as JSON
+  --max-states <N>    positions to try before giving up on solving a level [default: {}]
+  -h, --help          print this help
+
+Exits with 0 when there are no errors, 1 when there are, and 2 when the
+arguments or files can't be read.",
+        DEFAULT_MAX_STATES
+    )
+}
+
+/// what was asked for on the command line
+struct Args {
+    json: bool,
+    max_states: usize,
+    paths: Vec<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        json: false,
+        max_states: DEFAULT_MAX_STATES,
+        paths: vec![],
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--json" => parsed.json = true,
+            "--max-states" => {
+                let value = args.next().ok_or("--max-states needs a value")?;
+                parsed.max_states = value
+                    .parse()
+                    .map_err(|_| format!("--max-states must be a number, got \"{}\"", value))?;
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown option {}", flag)),
+            path => parsed.paths.push(path.to_string()),
+        }
+    }
+
+    if parsed.paths.is_empty() {
+        return Err("no packs given".to_string());
+    }
+    Ok(Some(parsed))
+}
+
+/// reads every pack file, expanding dirs into the TOML files in them
+fn read_packs(paths: &[String]) -> Result<Vec<(String, String)>, String> {
+    let mut files = vec![];
+    for path in paths {
+        if Path::new(path).is_dir() {
+            files.extend(pack_files_in_dir(Path::new(path)));
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    files
+        .into_iter()
+        .map(|file| {
+            std::fs::read_to_string(&file)
+                .map(|contents| (file.clone(), contents))
+                .map_err(|e| format!("unable to read {}: {}", file, e))
+        })
+        .collect()
+}
+
+fn count(diagnostics: &[Diagnostic], severity: Severity) -> usize {
+    diagnostics
+        .iter()
+        .filter(|d| d.severity == severity)
+        .count()
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", usage());
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, usage());
+            return ExitCode::from(2);
+        }
+    };
+
+    let files = match read_packs(&args.paths) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let diagnostics = lint_packs(&files, args.max_states);
+    let errors = count(&diagnostics, Severity::Error);
+    let warnings = count(&diagnostics, Severity::Warning);
+
+    if args.json {
+        let output = serde_json::json!({
+            "packs": files.len(),
+            "errors": errors,
+            "warnings": warnings,
+            "diagnostics": diagnostics,
+        });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{}", diagnostic);
+        }
+        println!(
+            "checked {} packs: {} errors, {} warnings",
+            files.len(),
+            errors,
+            warnings
+        );
+    }
+
+    if errors > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
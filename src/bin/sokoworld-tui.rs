@@ -0,0 +1,541 @@
+//! plays SokoWorld in a terminal, like over SSH or without a GPU, see `--help`
+
+#[cfg(not(target_family = "wasm"))]
+fn main() -> std::process::ExitCode {
+    tui::run()
+}
+
+// there's no terminal on the web, but every bin is built for it
+#[cfg(target_family = "wasm")]
+fn main() {}
+
+#[cfg(not(target_family = "wasm"))]
+mod tui {
+    use std::io::{self, Write};
+    use std::path::Path;
+    use std::process::ExitCode;
+    use std::time::{Duration, Instant};
+
+    use crossterm::cursor::{Hide, MoveTo, Show};
+    use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+    use crossterm::style::{Color, Print, PrintStyledContent, Stylize};
+    use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+    use crossterm::{execute, queue};
+
+    use sokoworld::achievement::{self, AchievementList};
+    use sokoworld::assets_path::determine_asset_path;
+    use sokoworld::cli::{self, Command, Invocation, LevelChoice};
+    use sokoworld::level::pack::Pack;
+    use sokoworld::level::playable_level::{Direction, MoveResult, PlayableLevel};
+    use sokoworld::profile::Profiles;
+    use sokoworld::save::Save;
+    use sokoworld::storage;
+    use sokoworld::vec2::Vec2;
+
+    /// how long to wait for a key before checking on play time again
+    const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+    fn usage() -> String {
+        format!(
+            "{}\n\nProgress is shared with the game, using the active profile.",
+            cli::usage_for("Plays SokoWorld in the terminal", "sokoworld-tui", &[])
+        )
+    }
+
+    /// what's shown in the terminal
+    enum Screen {
+        Packs,
+        Levels,
+        Playing(Box<PlayableLevel>),
+    }
+
+    struct App {
+        packs: Vec<Pack>,
+        /// the packs that failed to load, as file and reason
+        broken: Vec<String>,
+        save: Save,
+        achievements: AchievementList,
+        screen: Screen,
+        pack_index: usize,
+        level_index: usize,
+        /// shown under the current screen until the next key press
+        message: Option<String>,
+        quit: bool,
+    }
+
+    pub fn run() -> ExitCode {
+        let cli = match cli::init(&[]) {
+            Ok(Invocation::Run(cli)) => cli,
+            Ok(Invocation::Help) => {
+                println!("{}", usage());
+                return ExitCode::SUCCESS;
+            }
+            Ok(Invocation::Version) => {
+                println!("{}", cli::version());
+                return ExitCode::SUCCESS;
+            }
+            Err(err) => {
+                eprintln!("error: {}\n\nRun with --help to see the options", err);
+                return ExitCode::from(2);
+            }
+        };
+
+        let assets_path = determine_asset_path();
+        let discovered = Pack::read_all(&assets_path);
+        let profiles = Profiles::load(storage::data_storage());
+        let mut app = App {
+            packs: discovered.packs,
+            broken: discovered
+                .broken
+                .iter()
+                .map(|b| format!("{}: {}", b.file, b.reason))
+                .collect(),
+            save: Save::load(profiles.storage(), &profiles.active().id),
+            achievements: AchievementList::read(&assets_path),
+            screen: Screen::Packs,
+            pack_index: 0,
+            level_index: 0,
+            message: None,
+            quit: false,
+        };
+
+        if let Some(Command::Play { pack, level }) = &cli.command {
+            if let Err(err) = app.start_with(pack, level.as_ref()) {
+                eprintln!("error: {}\n\nRun with --help to see the options", err);
+                return ExitCode::from(2);
+            }
+        }
+
+        let result = with_terminal(|out| app.run_loop(out));
+        app.save.persist();
+        match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                ExitCode::FAILURE
+            }
+        }
+    }
+
+    /// sets the terminal up for drawing the game, putting it back how it was afterwards, even
+    /// if the game panics
+    fn with_terminal(f: impl FnOnce(&mut io::Stdout) -> io::Result<()>) -> io::Result<()> {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let _ = restore_terminal();
+            default_hook(info);
+        }));
+
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        let result = f(&mut out);
+        restore_terminal()?;
+        result
+    }
+
+    fn restore_terminal() -> io::Result<()> {
+        execute!(io::stdout(), Show, LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()
+    }
+
+    impl App {
+        /// jumps into the pack from the play command, loading it from its file if there's one
+        /// at the path, otherwise finding the pack with the slug
+        fn start_with(&mut self, pack: &str, level: Option<&LevelChoice>) -> Result<(), String> {
+            if Path::new(pack).is_file() {
+                let pack = Pack::read(pack)
+                    .map_err(|err| format!("unable to load pack {}: {}", pack, err))?;
+                self.packs.retain(|p| p.slug != pack.slug);
+                self.packs.push(pack);
+                self.pack_index = self.packs.len() - 1;
+            } else {
+                self.pack_index = self
+                    .packs
+                    .iter()
+                    .position(|p| p.slug == pack)
+                    .ok_or(format!(
+                        "no pack file or pack with the slug \"{}\" was found",
+                        pack
+                    ))?;
+            }
+
+            let level_index = match level {
+                Some(level) => level.index_in(&self.packs[self.pack_index])?,
+                None => 0,
+            };
+            self.play(level_index);
+            Ok(())
+        }
+
+        fn run_loop(&mut self, out: &mut impl Write) -> io::Result<()> {
+            let mut last_tick = Instant::now();
+            // redrawing only when something changed keeps the terminal from flickering
+            let mut redraw = true;
+            while !self.quit {
+                if redraw {
+                    self.draw(out)?;
+                    redraw = false;
+                }
+
+                if event::poll(INPUT_POLL_INTERVAL)? {
+                    match event::read()? {
+                        // some terminals report key releases too, which shouldn't move twice
+                        Event::Key(key) if key.kind != KeyEventKind::Release => {
+                            self.message = None;
+                            self.handle_key(key);
+                            redraw = true;
+                        }
+                        Event::Resize(_, _) => redraw = true,
+                        _ => {}
+                    }
+                }
+
+                let elapsed = last_tick.elapsed().as_secs_f32();
+                last_tick = Instant::now();
+                if let Screen::Playing(level) = &self.screen {
+                    if !level.complete {
+                        let pack_slug = &self.packs[self.pack_index].slug;
+                        self.save
+                            .record_play_time(pack_slug, &level.level.title, elapsed);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        fn handle_key(&mut self, key: KeyEvent) {
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                self.quit = true;
+                return;
+            }
+
+            match self.screen {
+                Screen::Packs => self.handle_packs_key(key.code),
+                Screen::Levels => self.handle_levels_key(key.code),
+                Screen::Playing(_) => self.handle_playing_key(key.code),
+            }
+        }
+
+        fn handle_packs_key(&mut self, code: KeyCode) {
+            match code {
+                KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('w') => {
+                    self.pack_index = self.pack_index.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('s')
+                    if self.pack_index + 1 < self.packs.len() =>
+                {
+                    self.pack_index += 1;
+                }
+                KeyCode::Enter | KeyCode::Right | KeyCode::Char(' ') => {
+                    let Some(pack) = self.packs.get(self.pack_index) else {
+                        return;
+                    };
+                    match self.unlock_text(pack) {
+                        Some(text) => self.message = Some(text),
+                        None => {
+                            self.level_index = 0;
+                            self.screen = Screen::Levels;
+                        }
+                    }
+                }
+                KeyCode::Esc | KeyCode::Char('q') => self.quit = true,
+                _ => {}
+            }
+        }
+
+        fn handle_levels_key(&mut self, code: KeyCode) {
+            let pack = &self.packs[self.pack_index];
+            match code {
+                KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('w') => {
+                    self.level_index = self.level_index.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('s')
+                    if self.level_index + 1 < pack.levels.len() =>
+                {
+                    self.level_index += 1;
+                }
+                KeyCode::Enter | KeyCode::Right | KeyCode::Char(' ') => {
+                    if pack.is_level_unlocked(&self.save, self.level_index) {
+                        self.play(self.level_index);
+                    } else {
+                        self.message = Some("That level is locked".to_string());
+                    }
+                }
+                KeyCode::Esc | KeyCode::Left | KeyCode::Char('q') => {
+                    self.save.persist();
+                    self.screen = Screen::Packs;
+                }
+                _ => {}
+            }
+        }
+
+        fn handle_playing_key(&mut self, code: KeyCode) {
+            let Screen::Playing(level) = &mut self.screen else {
+                return;
+            };
+            let pack = &self.packs[self.pack_index];
+
+            match code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.save.persist();
+                    self.screen = Screen::Levels;
+                }
+                KeyCode::Enter | KeyCode::Char('n') if level.complete => {
+                    if self.level_index + 1 < pack.levels.len() {
+                        self.play(self.level_index + 1);
+                    } else {
+                        self.save.persist();
+                        self.screen = Screen::Levels;
+                    }
+                }
+                _ if level.complete => {}
+                KeyCode::Char('r') | KeyCode::Char('c') => {
+                    level.reset();
+                    self.save.record_attempt(&pack.slug, &level.level.title);
+                }
+                KeyCode::Char('z')
+                | KeyCode::Char('u')
+                | KeyCode::Char('x')
+                | KeyCode::Backspace => {
+                    if level.rewind() == MoveResult::Blocked {
+                        self.message = Some("Nothing to rewind".to_string());
+                    }
+                }
+                code => {
+                    let Some(direction) = direction_for(code) else {
+                        return;
+                    };
+                    let result = level.make_move(&direction.movement());
+                    let mut unlocked = vec![];
+                    if result != MoveResult::Blocked {
+                        self.save
+                            .record_step(matches!(result, MoveResult::Pushed(_)));
+                        unlocked.extend(
+                            self.achievements
+                                .unlock_met(&mut self.save, &achievement::Event::Step),
+                        );
+                    }
+                    if level.complete {
+                        self.save.complete_level(
+                            pack.slug.clone(),
+                            level.level.title.clone(),
+                            level.steps,
+                            level.pushes,
+                            level.medal,
+                        );
+                        let event = achievement::Event::LevelCompleted {
+                            pack,
+                            level_title: &level.level.title,
+                            steps: level.steps,
+                            pushes: level.pushes,
+                            rewound: level.rewound,
+                        };
+                        unlocked.extend(self.achievements.unlock_met(&mut self.save, &event));
+                        self.save.persist();
+                    }
+                    if !unlocked.is_empty() {
+                        let titles: Vec<&str> = unlocked.iter().map(|a| a.title.as_str()).collect();
+                        self.message = Some(format!("Achievement unlocked: {}", titles.join(", ")));
+                    }
+                }
+            }
+        }
+
+        /// starts the level at the index of the current pack, recording the attempt
+        fn play(&mut self, level_index: usize) {
+            let pack = &self.packs[self.pack_index];
+            let pack_level = &pack.levels[level_index];
+            self.level_index = level_index;
+            self.save.record_attempt(&pack.slug, &pack_level.title);
+            self.screen =
+                Screen::Playing(Box::new(PlayableLevel::new(pack.slug.clone(), pack_level)));
+        }
+
+        /// what needs to be done to play the pack, if it's locked
+        fn unlock_text(&self, pack: &Pack) -> Option<String> {
+            pack.unlock_text(&self.save, &self.packs)
+        }
+
+        fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+            queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+            match &self.screen {
+                Screen::Packs => self.draw_packs(out)?,
+                Screen::Levels => self.draw_levels(out)?,
+                Screen::Playing(level) => self.draw_level(out, level)?,
+            }
+            if let Some(message) = &self.message {
+                queue!(
+                    out,
+                    Print("\r\n"),
+                    PrintStyledContent(message.as_str().yellow())
+                )?;
+            }
+            out.flush()
+        }
+
+        fn draw_packs(&self, out: &mut impl Write) -> io::Result<()> {
+            queue!(
+                out,
+                PrintStyledContent("SokoWorld".bold()),
+                Print("\r\n\r\n")
+            )?;
+            if self.packs.is_empty() {
+                queue!(out, Print("No packs found\r\n"))?;
+            }
+            for (i, pack) in self.packs.iter().enumerate() {
+                let line = match self.unlock_text(pack) {
+                    Some(text) => format!("{} ({})", pack.title, text),
+                    None => format!(
+                        "{} ({}/{} complete)",
+                        pack.title,
+                        self.save.completed_level_count_in_pack(&pack.slug),
+                        pack.levels.len()
+                    ),
+                };
+                let color = if self.unlock_text(pack).is_some() {
+                    Color::DarkGrey
+                } else {
+                    Color::Reset
+                };
+                draw_menu_line(out, &line, i == self.pack_index, color)?;
+            }
+            if !self.broken.is_empty() {
+                queue!(out, Print("\r\n"))?;
+                for broken in &self.broken {
+                    let line = format!("Broken pack {}\r\n", broken);
+                    queue!(out, PrintStyledContent(line.as_str().red()))?;
+                }
+            }
+            draw_help(out, "up/down choose | enter open | q quit")
+        }
+
+        fn draw_levels(&self, out: &mut impl Write) -> io::Result<()> {
+            let pack = &self.packs[self.pack_index];
+            queue!(
+                out,
+                PrintStyledContent(pack.title.as_str().bold()),
+                Print("\r\n\r\n")
+            )?;
+
+            let completed = pack.completed_levels(&self.save);
+            for (i, level) in pack.levels.iter().enumerate() {
+                let is_level_unlocked = pack.progression.is_level_unlocked(&completed, i);
+                let (label, color) = if completed[i] {
+                    let label = match self.save.level_medal(&pack.slug, &level.title) {
+                        Some(medal) => format!("complete - {}", medal.to_string().to_lowercase()),
+                        None => "complete".to_string(),
+                    };
+                    (label, Color::Blue)
+                } else if !is_level_unlocked {
+                    ("locked".to_string(), Color::DarkGrey)
+                } else {
+                    (String::new(), Color::Reset)
+                };
+                let line = match label.is_empty() {
+                    true => level.title.clone(),
+                    false => format!("{} ({})", level.title, label),
+                };
+                draw_menu_line(out, &line, i == self.level_index, color)?;
+            }
+            draw_help(out, "up/down choose | enter play | esc back")
+        }
+
+        fn draw_level(&self, out: &mut impl Write, level: &PlayableLevel) -> io::Result<()> {
+            let pack = &self.packs[self.pack_index];
+            queue!(
+                out,
+                PrintStyledContent(pack.title.as_str().bold()),
+                Print(" - "),
+                PrintStyledContent(level.level.title.as_str().bold()),
+                Print("\r\n\r\n")
+            )?;
+
+            for y in 0..level.level.height as i32 {
+                for x in 0..level.level.width as i32 {
+                    let pos = Vec2 { x, y };
+                    let (tile, color) = tile_at(level, &pos);
+                    // two columns per tile so levels aren't squashed by tall terminal cells
+                    let cell = format!("{} ", tile);
+                    queue!(out, PrintStyledContent(cell.as_str().with(color)))?;
+                }
+                queue!(out, Print("\r\n"))?;
+            }
+
+            queue!(
+                out,
+                Print("\r\n"),
+                Print(level.progress_text()),
+                Print("\r\n")
+            )?;
+            if level.complete {
+                let message = match level.medal {
+                    Some(medal) => {
+                        format!("Nice job, {} medal! Press enter for the next level.", medal)
+                    }
+                    None => "Nice job! Press enter for the next level.".to_string(),
+                };
+                queue!(
+                    out,
+                    PrintStyledContent(message.as_str().blue()),
+                    Print("\r\n")
+                )?;
+            }
+            draw_help(
+                out,
+                "arrows/wasd/hjkl move | z/x rewind | r/c reset | esc levels",
+            )
+        }
+    }
+
+    /// the char and color for what's at the position, in the usual Sokoban notation
+    fn tile_at(level: &PlayableLevel, pos: &Vec2) -> (char, Color) {
+        let is_goal = level.level.storage_locations.contains(pos);
+        if level.player.pos == *pos {
+            (if is_goal { '+' } else { '@' }, Color::Cyan)
+        } else if let Some(c) = level.crates.iter().find(|c| c.pos == *pos) {
+            match c.on_storage_location {
+                true => ('*', Color::Green),
+                false => ('$', Color::Yellow),
+            }
+        } else if is_goal {
+            ('.', Color::Red)
+        } else if level.level.walls.contains(pos) {
+            ('#', Color::DarkGrey)
+        } else {
+            (' ', Color::Reset)
+        }
+    }
+
+    fn direction_for(code: KeyCode) -> Option<Direction> {
+        match code {
+            KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => Some(Direction::Up),
+            KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('j') => Some(Direction::Down),
+            KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('h') => Some(Direction::Left),
+            KeyCode::Right | KeyCode::Char('d') | KeyCode::Char('l') => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    fn draw_menu_line(
+        out: &mut impl Write,
+        line: &str,
+        focused: bool,
+        color: Color,
+    ) -> io::Result<()> {
+        match focused {
+            true => queue!(out, PrintStyledContent(format!("> {}", line).red().bold()))?,
+            false => queue!(out, PrintStyledContent(format!("  {}", line).with(color)))?,
+        }
+        queue!(out, Print("\r\n"))
+    }
+
+    fn draw_help(out: &mut impl Write, help: &str) -> io::Result<()> {
+        queue!(
+            out,
+            Print("\r\n"),
+            PrintStyledContent(help.dark_grey()),
+            Print("\r\n")
+        )
+    }
+}
@@ -0,0 +1,390 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::consts::{PKG_NAME, VERSION};
+use crate::level::pack::Pack;
+
+static ARGS: OnceLock<Cli> = OnceLock::new();
+
+/// options the game was started with from the command line
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Cli {
+    /// where the game's assets are, instead of finding them
+    pub assets: Option<PathBuf>,
+    /// where saves and settings are kept, instead of the OS's user dirs
+    pub data_dir: Option<PathBuf>,
+    pub fullscreen: bool,
+    pub mute: bool,
+    /// where to write the input of the session as it's played
+    pub record: Option<PathBuf>,
+    /// a recorded session to play back instead of reading the keyboard and gamepads
+    pub replay: Option<PathBuf>,
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// jump straight into a pack, skipping the menus
+    Play {
+        /// file path or slug of the pack
+        pack: String,
+        level: Option<LevelChoice>,
+    },
+}
+
+/// which level of a pack to start at
+#[derive(Debug, Clone, PartialEq)]
+pub enum LevelChoice {
+    /// the level's number in the pack, starting from 1
+    Number(usize),
+    Title(String),
+}
+
+/// what the args asked the game to do
+#[derive(Debug, PartialEq)]
+pub enum Invocation {
+    Run(Cli),
+    Help,
+    Version,
+}
+
+impl LevelChoice {
+    /// index of the chosen level in the pack, or why it isn't there
+    pub fn index_in(&self, pack: &Pack) -> Result<usize, String> {
+        match self {
+            LevelChoice::Number(number) if *number <= pack.levels.len() => Ok(number - 1),
+            LevelChoice::Number(number) => Err(format!(
+                "{} has {} levels, so there's no level {}",
+                pack.title,
+                pack.levels.len(),
+                number
+            )),
+            LevelChoice::Title(title) => pack
+                .levels
+                .iter()
+                .position(|l| l.title == *title)
+                .ok_or(format!("{} has no level titled \"{}\"", pack.title, title)),
+        }
+    }
+}
+
+/// the help line for each option only the game takes, starting with the option; other programs
+/// that share these args, like the terminal version, reject them
+pub const GAME_OPTIONS: [&str; 4] = [
+    "  --fullscreen            start in fullscreen",
+    "  --mute                  start with sound muted",
+    "  --record <FILE>         write every frame's input to the file to replay later",
+    "  --replay <FILE>         play back input recorded with --record",
+];
+
+/// the option a help line is for, like `--record` for the line about `--record <FILE>`
+fn option_name(help_line: &str) -> &str {
+    help_line.split_whitespace().next().unwrap_or_default()
+}
+
+pub fn usage() -> String {
+    usage_for(
+        "SokoWorld, the classic box pushing puzzler",
+        PKG_NAME,
+        &GAME_OPTIONS,
+    )
+}
+
+/// the help for a program that takes these args, like the terminal version, listing its own
+/// options along with the ones every program takes
+pub fn usage_for(about: &str, program: &str, own_options: &[&str]) -> String {
+    let options: Vec<&str> = [
+        "  --assets <DIR>          where the game's assets are",
+        "  --data-dir <DIR>        where saves and settings are kept",
+    ]
+    .into_iter()
+    .chain(own_options.iter().copied())
+    .chain([
+        "  -h, --help              print this help",
+        "  -V, --version           print the version",
+    ])
+    .collect();
+    format!(
+        "{}
+
+Usage: {} [OPTIONS] [COMMAND]
+
+Commands:
+  play <PACK>             play a pack, given as a file path or slug, skipping the menus
+    --level <N>           start at the Nth level of the pack
+    --level-title <T>     start at the level with the title
+
+Options:
+{}",
+        about,
+        program,
+        options.join("\n")
+    )
+}
+
+pub fn version() -> String {
+    format!("{} {}", PKG_NAME, VERSION)
+}
+
+/// parses the args, not including the program name, for a program that takes the game options
+/// in `own_options` along with the ones every program takes
+pub fn parse(args: &[String], own_options: &[&str]) -> Result<Invocation, String> {
+    let mut cli = Cli::default();
+    let mut pack: Option<String> = None;
+    let mut is_play = false;
+    let mut level_number: Option<usize> = None;
+    let mut level_title: Option<String> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // options can be given as `--foo bar` or `--foo=bar`
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || -> Result<String, String> {
+            match inline_value.clone().or_else(|| args.next().cloned()) {
+                Some(value) if !value.starts_with("--") => Ok(value),
+                _ => Err(format!("{} needs a value", name)),
+            }
+        };
+        let no_value = |name: &str| -> Result<(), String> {
+            match inline_value {
+                Some(_) => Err(format!("{} doesn't take a value", name)),
+                None => Ok(()),
+            }
+        };
+
+        let takes = |options: &[&str]| options.iter().any(|line| option_name(line) == name);
+        if takes(&GAME_OPTIONS) && !takes(own_options) {
+            return Err(format!("unknown option {}", name));
+        }
+
+        match name {
+            "-h" | "--help" => return Ok(Invocation::Help),
+            "-V" | "--version" => return Ok(Invocation::Version),
+            "--assets" => cli.assets = Some(PathBuf::from(value()?)),
+            "--data-dir" => cli.data_dir = Some(PathBuf::from(value()?)),
+            "--fullscreen" => {
+                no_value(name)?;
+                cli.fullscreen = true;
+            }
+            "--mute" => {
+                no_value(name)?;
+                cli.mute = true;
+            }
+            "--record" => cli.record = Some(PathBuf::from(value()?)),
+            "--replay" => cli.replay = Some(PathBuf::from(value()?)),
+            "--level" => {
+                let value = value()?;
+                match value.parse::<usize>() {
+                    Ok(number) if number > 0 => level_number = Some(number),
+                    _ => {
+                        return Err(format!(
+                            "--level must be a number starting from 1, got \"{}\"",
+                            value
+                        ))
+                    }
+                }
+            }
+            "--level-title" => level_title = Some(value()?),
+            option if option.starts_with('-') => {
+                return Err(format!("unknown option {}", option));
+            }
+            "play" if !is_play => is_play = true,
+            positional if is_play && pack.is_none() => pack = Some(positional.to_string()),
+            positional if !is_play => return Err(format!("unknown command \"{}\"", positional)),
+            positional => return Err(format!("unexpected argument \"{}\"", positional)),
+        }
+    }
+
+    if cli.record.is_some() && cli.replay.is_some() {
+        return Err("use either --record or --replay, not both".into());
+    }
+
+    let level = match (level_number, level_title) {
+        (Some(_), Some(_)) => return Err("use either --level or --level-title, not both".into()),
+        (Some(number), None) => Some(LevelChoice::Number(number)),
+        (None, Some(title)) => Some(LevelChoice::Title(title)),
+        (None, None) => None,
+    };
+    if is_play {
+        let pack = pack.ok_or("play needs a pack")?;
+        cli.command = Some(Command::Play { pack, level });
+    } else if level.is_some() {
+        return Err("--level and --level-title only work with the play command".into());
+    }
+
+    Ok(Invocation::Run(cli))
+}
+
+/// parses the args the program was started with, keeping them for `args()`
+pub fn init(own_options: &[&str]) -> Result<Invocation, String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let invocation = parse(&args, own_options)?;
+    if let Invocation::Run(cli) = &invocation {
+        ARGS.get_or_init(|| cli.clone());
+    }
+    Ok(invocation)
+}
+
+/// the options the game was started with; all defaults if `init()` wasn't called
+pub fn args() -> &'static Cli {
+    ARGS.get_or_init(Cli::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Result<Cli, String> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        match parse(&args, &GAME_OPTIONS)? {
+            Invocation::Run(cli) => Ok(cli),
+            other => panic!("expected to run, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_usage_shares_commands_and_options() {
+        let game = usage();
+        let tui = usage_for("Plays it in the terminal", "sokoworld-tui", &[]);
+        assert!(tui.starts_with("Plays it in the terminal\n\nUsage: sokoworld-tui [OPTIONS]"));
+        // everything after the line about the program and its name
+        for line in tui.lines().skip(3) {
+            assert!(game.contains(line), "{}", line);
+        }
+        assert!(game.contains("  --mute "));
+        assert!(!tui.contains("--mute"));
+        assert!(tui.ends_with("  -V, --version           print the version"));
+    }
+
+    #[test]
+    fn test_parses_equal_and_space_values() {
+        let cli = run(&["--assets=/a", "--data-dir", "/d", "--mute"]).unwrap();
+        assert_eq!(cli.assets, Some(PathBuf::from("/a")));
+        assert_eq!(cli.data_dir, Some(PathBuf::from("/d")));
+        assert!(cli.mute);
+        assert!(!cli.fullscreen);
+        assert_eq!(cli.command, None);
+
+        let cli = run(&["--replay=bug.jsonl"]).unwrap();
+        assert_eq!(cli.replay, Some(PathBuf::from("bug.jsonl")));
+    }
+
+    #[test]
+    fn test_parses_play() {
+        let cli = run(&["--fullscreen", "play", "pack-a", "--level", "3"]).unwrap();
+        assert!(cli.fullscreen);
+        assert_eq!(
+            cli.command,
+            Some(Command::Play {
+                pack: "pack-a".to_string(),
+                level: Some(LevelChoice::Number(3)),
+            })
+        );
+
+        let cli = run(&["play", "--level-title=Level 2", "a.toml"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Command::Play {
+                pack: "a.toml".to_string(),
+                level: Some(LevelChoice::Title("Level 2".to_string())),
+            })
+        );
+    }
+
+    #[test]
+    fn test_help_and_version() {
+        let args = vec!["play".to_string(), "--help".to_string()];
+        assert_eq!(parse(&args, &[]), Ok(Invocation::Help));
+        assert_eq!(parse(&["-V".to_string()], &[]), Ok(Invocation::Version));
+    }
+
+    #[test]
+    fn test_rejects_game_options_for_other_programs() {
+        for args in [vec!["--mute"], vec!["--record", "x"], vec!["--replay=x"]] {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            let name = args[0].split('=').next().unwrap();
+            assert_eq!(parse(&args, &[]), Err(format!("unknown option {}", name)));
+        }
+        assert!(parse(&["--data-dir".to_string(), "/d".to_string()], &[]).is_ok());
+        assert_eq!(run(&["--mute"]).map(|cli| cli.mute), Ok(true));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            run(&["--assets"]),
+            Err("--assets needs a value".to_string())
+        );
+        assert_eq!(
+            run(&["--assets", "--mute"]),
+            Err("--assets needs a value".to_string())
+        );
+        assert_eq!(
+            run(&["--mute=yes"]),
+            Err("--mute doesn't take a value".to_string())
+        );
+        assert_eq!(
+            run(&["--record", "a.jsonl", "--replay", "b.jsonl"]),
+            Err("use either --record or --replay, not both".to_string())
+        );
+        assert_eq!(run(&["--nope"]), Err("unknown option --nope".to_string()));
+        assert_eq!(run(&["plya"]), Err("unknown command \"plya\"".to_string()));
+        assert_eq!(run(&["play"]), Err("play needs a pack".to_string()));
+        assert_eq!(
+            run(&["play", "a", "b"]),
+            Err("unexpected argument \"b\"".to_string())
+        );
+        assert_eq!(
+            run(&["play", "a", "--level", "0"]),
+            Err("--level must be a number starting from 1, got \"0\"".to_string())
+        );
+        assert_eq!(
+            run(&["play", "a", "--level", "1", "--level-title", "x"]),
+            Err("use either --level or --level-title, not both".to_string())
+        );
+        assert_eq!(
+            run(&["--level", "1"]),
+            Err("--level and --level-title only work with the play command".to_string())
+        );
+    }
+
+    #[test]
+    fn test_level_choice_index() {
+        let pack = Pack::parse(
+            r#"
+title = "Test"
+slug = "test"
+description = ""
+author = ""
+license = ""
+difficulty = "Easy"
+year = 2024
+version = "0.1"
+
+[[levels]]
+title = "First"
+data = '''
+#####
+#@$.#
+#####
+'''
+"#,
+        )
+        .unwrap();
+        assert_eq!(LevelChoice::Number(1).index_in(&pack), Ok(0));
+        assert_eq!(
+            LevelChoice::Number(2).index_in(&pack),
+            Err("Test has 1 levels, so there's no level 2".to_string())
+        );
+        assert_eq!(
+            LevelChoice::Title("First".to_string()).index_in(&pack),
+            Ok(0)
+        );
+        assert!(LevelChoice::Title("Nope".to_string())
+            .index_in(&pack)
+            .is_err());
+    }
+}
@@ -13,3 +13,19 @@ pub const BLUE: Color = Color {
     b: 0.95,
     a: 1.0,
 };
+
+/// for things that are locked, like achievements and levels
+pub const GRAY: Color = Color {
+    r: 0.6,
+    g: 0.6,
+    b: 0.6,
+    a: 1.0,
+};
+
+/// drawn over a scene to dim it when a scene is shown over it, like the pause menu
+pub const OVERLAY: Color = Color {
+    r: 0.15,
+    g: 0.15,
+    b: 0.15,
+    a: 0.85,
+};
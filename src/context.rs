@@ -1,12 +1,20 @@
+use crate::achievement::AchievementList;
 use crate::assets_path::determine_asset_path;
 use crate::audio;
+use crate::cli;
 use crate::consts::*;
 use crate::font;
+use crate::input::{Input, InputLayer};
+use crate::level::pack::{DiscoveredPacks, Pack};
+use crate::profile::Profiles;
 use crate::save::Save;
-use crate::scene::EScene;
+use crate::scene::{Scene, SceneChange};
 use crate::settings::Settings;
+use crate::storage;
 use crate::texture;
 use crate::tile;
+use crate::toast::Toasts;
+use crate::transition::TransitionKind;
 use gamepads::Gamepads;
 use macroquad::math::Rect;
 use macroquad::miniquad::FilterMode;
@@ -18,6 +26,10 @@ use macroquad::{camera::Camera2D, texture::RenderTarget};
 pub struct Context {
     pub request_quit: bool,
     pub gamepads: Gamepads,
+    /// the actions for this frame, from the player or a recording
+    pub input: Input,
+    /// which bindings are checked, set to the layer of the scene being updated
+    pub input_layer: InputLayer,
     pub textures: texture::TextureAtlas,
     pub fonts: font::FontAtlas,
     pub audio: audio::AudioAtlas,
@@ -25,7 +37,12 @@ pub struct Context {
     pub render_target_cam: Camera2D,
     pub load_next_level: bool,
     pub tileset: tile::Tileset,
-    pub switch_scene_to: Option<EScene>,
+    /// changes to the scene stack asked for this frame, made by main once it's done
+    pub scene_changes: Vec<SceneChange>,
+    /// the transition for this frame's scene changes, instead of the one that fits the change
+    pub scene_transition: Option<TransitionKind>,
+    /// every pack that could be found, along with the ones that couldn't be loaded
+    pub packs: DiscoveredPacks,
     /// whether or not to reload the level from disk at the end of the current game loop
     pub reload_level: bool,
     /// what pack is currently being played, if any. needed for reloading from disk
@@ -33,7 +50,12 @@ pub struct Context {
     /// what level is currently being played, if any. needed for reloading from disk
     pub current_level_index: Option<usize>,
     pub settings: Settings,
+    pub profiles: Profiles,
+    /// progress of the active profile
     pub save: Save,
+    pub achievements: AchievementList,
+    /// messages shown over every scene, like unlocked achievements
+    pub toasts: Toasts,
 }
 
 impl Context {
@@ -47,9 +69,16 @@ impl Context {
         render_target_cam.render_target = Some(render_target.clone());
 
         let base_assets_path = determine_asset_path();
+        let profiles = Profiles::load(storage::data_storage());
+        let save = Save::load(profiles.storage(), &profiles.active().id);
+
+        let mut settings = Settings::load(storage::config_storage());
+        settings.override_for_session(cli::args().fullscreen, cli::args().mute);
 
         Self {
             gamepads: Gamepads::new(),
+            input: Input::default(),
+            input_layer: InputLayer::Menu,
             request_quit: false,
             textures: texture::TextureAtlas::new(&base_assets_path).await,
             audio: audio::AudioAtlas::new(&base_assets_path).await,
@@ -59,12 +88,36 @@ impl Context {
             reload_level: false,
             render_target_cam,
             tileset: tile::Tileset::Doggo,
-            switch_scene_to: None,
+            scene_changes: vec![],
+            scene_transition: None,
+            packs: Pack::load_all(&base_assets_path).await,
             current_pack_file: None,
             current_level_index: None,
-            settings: Settings::load(),
-            save: Save::load(),
+            settings,
+            profiles,
+            save,
+            achievements: AchievementList::new(&base_assets_path).await,
+            toasts: Toasts::new(),
+        }
+    }
+
+    /// makes the profile the active one and loads its save
+    pub fn switch_profile(&mut self, profile_id: &str) {
+        self.save.persist();
+        self.profiles.select(profile_id);
+        self.save = Save::load(self.profiles.storage(), &self.profiles.active().id);
+    }
+
+    /// deletes the profile and its save, loading the save of whichever profile becomes active
+    ///
+    /// returns whether or not the profile was deleted
+    pub fn delete_profile(&mut self, profile_id: &str) -> bool {
+        self.save.persist();
+        let deleted = self.profiles.delete(profile_id);
+        if deleted {
+            self.save = Save::load(self.profiles.storage(), &self.profiles.active().id);
         }
+        deleted
     }
 
     pub fn current_texture(&self) -> &Texture2D {
@@ -75,4 +128,39 @@ impl Context {
             tile::Tileset::Marble => &self.textures.marble,
         }
     }
+
+    /// opens the scene on top of the current one once the frame is done
+    pub fn push_scene(&mut self, scene: impl Scene + 'static) {
+        self.scene_changes.push(SceneChange::Push(Box::new(scene)));
+    }
+
+    /// closes the current scene once the frame is done, going back to the one below
+    pub fn pop_scene(&mut self) {
+        self.scene_changes.push(SceneChange::Pop);
+    }
+
+    /// swaps the current scene for this one once the frame is done
+    pub fn replace_scene(&mut self, scene: impl Scene + 'static) {
+        self.scene_changes
+            .push(SceneChange::Replace(Box::new(scene)));
+    }
+
+    /// closes every scene and starts over with this one once the frame is done
+    pub fn reset_scenes(&mut self, scene: impl Scene + 'static) {
+        self.scene_changes.push(SceneChange::Reset(Box::new(scene)));
+    }
+
+    /// uses the transition for the scene changes asked for this frame
+    pub fn set_scene_transition(&mut self, kind: TransitionKind) {
+        self.scene_transition = Some(kind);
+    }
+
+    /// looks for packs again, picking up ones that were added or changed on disk; on web the
+    /// packs can't change while playing, so they're kept as they are
+    pub fn reload_packs(&mut self) {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            self.packs = Pack::read_all(&determine_asset_path());
+        }
+    }
 }
@@ -1,5 +1,23 @@
 #[cfg(not(target_family = "wasm"))]
+use crate::cli;
+#[cfg(not(target_family = "wasm"))]
+use crate::level::pack::PACKS_DIR;
+#[cfg(not(target_family = "wasm"))]
 use directories::ProjectDirs;
+#[cfg(not(target_family = "wasm"))]
+use std::env::current_exe;
+#[cfg(not(target_family = "wasm"))]
+use std::path::{Path, PathBuf};
+#[cfg(not(target_family = "wasm"))]
+use std::time::SystemTime;
+
+/// when this file is next to the executable, saves and settings are kept next to it too
+pub const PORTABLE_MARKER_FILE: &str = "portable.txt";
+/// dir next to the executable that saves and settings are kept in when in portable mode
+pub const PORTABLE_DATA_DIR: &str = "data";
+/// how often in seconds a watched file is checked for changes
+#[cfg(not(target_family = "wasm"))]
+const FILE_WATCH_INTERVAL: f32 = 0.5;
 
 #[cfg(not(target_family = "wasm"))]
 /// returns the ProjectDirs struct from the directories crate with the proper identifier for the
@@ -7,3 +25,162 @@ use directories::ProjectDirs;
 pub fn project_dirs() -> ProjectDirs {
     ProjectDirs::from("com", "brettchalupa", "sokoworld").unwrap()
 }
+
+/// Returns the dir that progress (profiles and saves) is kept in
+///
+/// Uses the overridden data dir if there is one (see `data_dir_override`), otherwise the OS's
+/// local data dir for the user.
+#[cfg(not(target_family = "wasm"))]
+pub fn data_dir() -> PathBuf {
+    data_dir_override().unwrap_or_else(|| PathBuf::from(project_dirs().data_local_dir()))
+}
+
+/// Returns the dir that settings are kept in
+///
+/// Uses the overridden data dir if there is one so that everything lives in one place,
+/// otherwise the OS's local config dir for the user.
+#[cfg(not(target_family = "wasm"))]
+pub fn config_dir() -> PathBuf {
+    data_dir_override().unwrap_or_else(|| PathBuf::from(project_dirs().config_local_dir()))
+}
+
+/// Returns the dir that players can put their own packs in
+#[cfg(not(target_family = "wasm"))]
+pub fn user_packs_dir() -> PathBuf {
+    data_dir().join(PACKS_DIR)
+}
+
+/// Returns the dir to keep saves and settings in instead of the OS's user dirs, if any
+///
+/// Checks, in this order:
+/// 0. was the `--data-dir` arg provided?
+/// 1. was the SOKOWORLD_DATA_DIR ENV set?
+/// 2. is there a `portable.txt` next to the executable? if so, uses the `data` dir next to it
+#[cfg(not(target_family = "wasm"))]
+pub fn data_dir_override() -> Option<PathBuf> {
+    resolve_data_dir_override(
+        cli::args().data_dir.clone(),
+        std::env::var("SOKOWORLD_DATA_DIR").ok(),
+        current_exe().ok().as_deref(),
+    )
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn resolve_data_dir_override(
+    arg_data_dir: Option<PathBuf>,
+    env_data_dir: Option<String>,
+    exe_path: Option<&Path>,
+) -> Option<PathBuf> {
+    if arg_data_dir.is_some() {
+        return arg_data_dir;
+    }
+
+    if let Some(env_data_dir) = env_data_dir {
+        return Some(PathBuf::from(env_data_dir));
+    }
+
+    let exe_dir = exe_path?.parent()?;
+    if exe_dir.join(PORTABLE_MARKER_FILE).is_file() {
+        return Some(exe_dir.join(PORTABLE_DATA_DIR));
+    }
+
+    None
+}
+
+/// notices when a file changes on disk by checking when it was last modified every so often
+#[cfg(not(target_family = "wasm"))]
+pub struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    /// seconds until the file is checked again
+    until_next_check: f32,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl FileWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            modified: Self::modified(&path),
+            path,
+            until_next_check: FILE_WATCH_INTERVAL,
+        }
+    }
+
+    /// counts down by dt, checking the file when it's time; returns whether the file changed
+    /// since the last check
+    pub fn changed(&mut self, dt: f32) -> bool {
+        self.until_next_check -= dt;
+        if self.until_next_check > 0. {
+            return false;
+        }
+        self.until_next_check = FILE_WATCH_INTERVAL;
+
+        let modified = Self::modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_dir_arg_wins() {
+        let data_dir = resolve_data_dir_override(
+            Some(PathBuf::from("/tmp/soko")),
+            Some(String::from("/tmp/env")),
+            None,
+        );
+        assert_eq!(data_dir, Some(PathBuf::from("/tmp/soko")));
+    }
+
+    #[test]
+    fn test_data_dir_env() {
+        let data_dir = resolve_data_dir_override(None, Some(String::from("/tmp/env")), None);
+        assert_eq!(data_dir, Some(PathBuf::from("/tmp/env")));
+    }
+
+    #[test]
+    fn test_portable_mode() {
+        let exe_dir =
+            std::env::temp_dir().join(format!("sokoworld-portable-{}", std::process::id()));
+        std::fs::create_dir_all(&exe_dir).unwrap();
+        let exe_path = exe_dir.join("sokoworld");
+
+        assert_eq!(resolve_data_dir_override(None, None, Some(&exe_path)), None);
+
+        std::fs::write(exe_dir.join(PORTABLE_MARKER_FILE), "").unwrap();
+        assert_eq!(
+            resolve_data_dir_override(None, None, Some(&exe_path)),
+            Some(exe_dir.join(PORTABLE_DATA_DIR))
+        );
+
+        std::fs::remove_dir_all(exe_dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_watcher() {
+        let path = std::env::temp_dir().join(format!("sokoworld-watch-{}", std::process::id()));
+        std::fs::write(&path, "one").unwrap();
+        let mut watcher = FileWatcher::new(&path);
+        assert!(!watcher.changed(FILE_WATCH_INTERVAL));
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(10))
+            .unwrap();
+        assert!(!watcher.changed(FILE_WATCH_INTERVAL / 2.));
+        assert!(watcher.changed(FILE_WATCH_INTERVAL / 2.));
+        assert!(!watcher.changed(FILE_WATCH_INTERVAL));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,7 +1,29 @@
-use gamepads::Gamepads;
+use std::collections::BTreeMap;
+
+use gamepads::{Button, Gamepads};
 use macroquad::input::KeyCode;
-use macroquad::input::{is_key_down, is_key_pressed};
+use macroquad::input::{
+    get_char_pressed, get_last_key_pressed, is_key_down, is_key_pressed, touches,
+};
+use macroquad::logging::warn;
+use macroquad::window::{screen_height, screen_width};
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+
+use crate::context::Context;
+use crate::settings::Settings;
+use recording::{FrameInput, Playback, Recorder};
+use repeat::Repeater;
+use stick::{Stick, StickDirection};
+use touch::TouchInput;
+
+pub mod recording;
+pub mod repeat;
+pub mod stick;
+pub mod touch;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Action {
     /// move up (player, menu, etc.)
     Up,
@@ -21,108 +43,961 @@ pub enum Action {
     Rewind,
     /// the gameplay and bring up a menu
     Pause,
+    /// load the level again from its pack file, for designing levels
+    ReloadLevel,
+    /// switch to the next tileset
+    CycleTileset,
 }
 
-/// just pressed, not held down
-pub fn action_pressed(action: Action, gamepads: &Gamepads) -> bool {
-    keyboard_pressed(&action) || gamepad_pressed(&action, gamepads)
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Confirm,
+        Action::Cancel,
+        Action::Reset,
+        Action::Rewind,
+        Action::Pause,
+        Action::ReloadLevel,
+        Action::CycleTileset,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "Up",
+            Action::Down => "Down",
+            Action::Left => "Left",
+            Action::Right => "Right",
+            Action::Confirm => "Confirm",
+            Action::Cancel => "Cancel",
+            Action::Reset => "Reset",
+            Action::Rewind => "Rewind",
+            Action::Pause => "Pause",
+            Action::ReloadLevel => "Reload Level",
+            Action::CycleTileset => "Cycle Tileset",
+        }
+    }
 }
 
-/// held down for multiple frames
-pub fn action_down(action: Action, gamepads: &Gamepads) -> bool {
-    keyboard_down(&action) || gamepad_down(&action, gamepads)
+/// a set of actions that are checked together, each with its own bindings, so the same key can
+/// mean different things in different places, like K rewinding in a level and going back in a
+/// menu
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputLayer {
+    Gameplay,
+    Menu,
+    /// tools for designing levels, checked while playing
+    Editor,
 }
 
-fn keyboard_pressed(action: &Action) -> bool {
-    match action {
-        Action::Up => is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up),
-        Action::Down => is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down),
-        Action::Left => is_key_pressed(KeyCode::A) || is_key_pressed(KeyCode::Left),
-        Action::Right => is_key_pressed(KeyCode::D) || is_key_pressed(KeyCode::Right),
-        Action::Rewind => is_key_pressed(KeyCode::K) || is_key_pressed(KeyCode::X),
-        Action::Reset => is_key_pressed(KeyCode::L) || is_key_pressed(KeyCode::C),
-        Action::Confirm => is_key_pressed(KeyCode::J) || is_key_pressed(KeyCode::Z),
-        Action::Cancel => is_key_pressed(KeyCode::K) || is_key_pressed(KeyCode::X),
-        Action::Pause => is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::P),
+impl InputLayer {
+    pub const ALL: [InputLayer; 3] = [InputLayer::Gameplay, InputLayer::Menu, InputLayer::Editor];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputLayer::Gameplay => "Gameplay",
+            InputLayer::Menu => "Menu",
+            InputLayer::Editor => "Editor",
+        }
+    }
+
+    /// the actions that can be bound in this layer
+    pub fn actions(&self) -> &'static [Action] {
+        match self {
+            InputLayer::Gameplay => &[
+                Action::Up,
+                Action::Down,
+                Action::Left,
+                Action::Right,
+                Action::Confirm,
+                Action::Reset,
+                Action::Rewind,
+                Action::Pause,
+            ],
+            InputLayer::Menu => &[
+                Action::Up,
+                Action::Down,
+                Action::Left,
+                Action::Right,
+                Action::Confirm,
+                Action::Cancel,
+                Action::Reset,
+                Action::Pause,
+            ],
+            InputLayer::Editor => &[Action::ReloadLevel, Action::CycleTileset],
+        }
     }
-}
 
-fn keyboard_down(action: &Action) -> bool {
-    match action {
-        Action::Up => is_key_down(KeyCode::W) || is_key_down(KeyCode::Up),
-        Action::Down => is_key_down(KeyCode::S) || is_key_down(KeyCode::Down),
-        Action::Left => is_key_down(KeyCode::A) || is_key_down(KeyCode::Left),
-        Action::Right => is_key_down(KeyCode::D) || is_key_down(KeyCode::Right),
-        Action::Reset => is_key_down(KeyCode::L) || is_key_down(KeyCode::C),
-        Action::Rewind => is_key_down(KeyCode::K) || is_key_down(KeyCode::X),
-        Action::Confirm => is_key_down(KeyCode::J) || is_key_down(KeyCode::Z),
-        Action::Cancel => is_key_down(KeyCode::K) || is_key_down(KeyCode::X),
-        Action::Pause => is_key_down(KeyCode::Escape) || is_key_down(KeyCode::P),
+    /// the first layer that has the action out of this one and the ones checked with it
+    pub fn layer_for(&self, action: Action) -> Option<InputLayer> {
+        self.active_with()
+            .iter()
+            .find(|layer| layer.actions().contains(&action))
+            .copied()
+    }
+
+    /// this layer and the ones checked at the same time as it, whose bindings can't overlap
+    pub fn active_with(&self) -> &'static [InputLayer] {
+        match self {
+            InputLayer::Gameplay | InputLayer::Editor => {
+                &[InputLayer::Gameplay, InputLayer::Editor]
+            }
+            InputLayer::Menu => &[InputLayer::Menu],
+        }
     }
 }
 
-/// checks the action for any of the connected gamepads
-fn gamepad_pressed(action: &Action, gamepads: &Gamepads) -> bool {
-    match action {
-        Action::Up => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::DPadUp)),
-        Action::Down => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::DPadDown)),
-        Action::Left => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::DPadLeft)),
-        Action::Right => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::DPadRight)),
-        Action::Confirm => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::ActionDown)),
-        Action::Cancel => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::ActionRight)),
-        Action::Rewind => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::ActionLeft)),
-        Action::Reset => gamepads
-            .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::ActionUp)),
-        Action::Pause => gamepads
+/// keys that can be bound to an action, which is every key but the ones only some keyboards have
+const BINDABLE_KEYS: [KeyCode; 76] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Kp0,
+    KeyCode::Kp1,
+    KeyCode::Kp2,
+    KeyCode::Kp3,
+    KeyCode::Kp4,
+    KeyCode::Kp5,
+    KeyCode::Kp6,
+    KeyCode::Kp7,
+    KeyCode::Kp8,
+    KeyCode::Kp9,
+    KeyCode::KpEnter,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Delete,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::LeftShift,
+    KeyCode::RightShift,
+    KeyCode::LeftControl,
+    KeyCode::RightControl,
+    KeyCode::LeftAlt,
+    KeyCode::RightAlt,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Apostrophe,
+    KeyCode::LeftBracket,
+    KeyCode::RightBracket,
+    KeyCode::Minus,
+];
+
+/// a key, gamepad button or way of pushing the left stick that triggers an action
+///
+/// stored in the settings as text, like `key:W`, `pad:DPadUp` or `stick:Up`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Binding {
+    Key(KeyCode),
+    /// on any connected gamepad
+    Button(Button),
+    /// the left stick of any connected gamepad
+    Stick(StickDirection),
+}
+
+impl Binding {
+    /// the key, gamepad button or stick direction pressed this frame
+    fn just_pressed(gamepads: &Gamepads, stick: &Stick) -> Option<Self> {
+        if let Some(key) = get_last_key_pressed().filter(|key| BINDABLE_KEYS.contains(key)) {
+            return Some(Binding::Key(key));
+        }
+        gamepads
             .all()
-            .any(|g| g.is_just_pressed(gamepads::Button::RightCenterCluster)),
+            .find_map(|g| g.all_just_pressed().next())
+            .map(Binding::Button)
+            .or(stick.just_pushed().map(Binding::Stick))
+    }
+
+    fn is_pressed(&self, gamepads: &Gamepads, stick: &Stick) -> bool {
+        match self {
+            Binding::Key(key) => is_key_pressed(*key),
+            Binding::Button(button) => gamepads.all().any(|g| g.is_just_pressed(*button)),
+            Binding::Stick(direction) => stick.is_pushed(*direction),
+        }
+    }
+
+    fn is_down(&self, gamepads: &Gamepads, stick: &Stick) -> bool {
+        match self {
+            Binding::Key(key) => is_key_down(*key),
+            Binding::Button(button) => gamepads.all().any(|g| g.is_currently_pressed(*button)),
+            Binding::Stick(direction) => stick.is_held(*direction),
+        }
+    }
+
+    /// shown to the player, like on the controls screen
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{:?}", key);
+                name.strip_prefix("Key").unwrap_or(&name).to_string()
+            }
+            Binding::Button(button) => format!("Pad {:?}", button),
+            Binding::Stick(direction) => format!("Stick {:?}", direction),
+        }
     }
 }
 
-/// checks the action for any of the connected gamepads
-fn gamepad_down(action: &Action, gamepads: &Gamepads) -> bool {
-    match action {
-        Action::Up => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::DPadUp)),
-        Action::Down => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::DPadDown)),
-        Action::Left => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::DPadLeft)),
-        Action::Right => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::DPadRight)),
-        Action::Confirm => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::ActionDown)),
-        Action::Cancel => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::ActionRight)),
-        Action::Rewind => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::ActionLeft)),
-        Action::Reset => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::ActionUp)),
-        Action::Pause => gamepads
-            .all()
-            .any(|g| g.is_currently_pressed(gamepads::Button::RightCenterCluster)),
+impl From<Binding> for String {
+    fn from(binding: Binding) -> Self {
+        match binding {
+            Binding::Key(key) => format!("key:{:?}", key),
+            Binding::Button(button) => format!("pad:{:?}", button),
+            Binding::Stick(direction) => format!("stick:{:?}", direction),
+        }
+    }
+}
+
+impl TryFrom<String> for Binding {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let unknown = || format!("unknown binding {}", text);
+        let (kind, name) = text.split_once(':').ok_or_else(unknown)?;
+        match kind {
+            "key" => BINDABLE_KEYS
+                .iter()
+                .find(|key| format!("{:?}", key) == name)
+                .map(|key| Binding::Key(*key)),
+            "pad" => Button::all()
+                .find(|button| format!("{:?}", button) == name)
+                .map(Binding::Button),
+            "stick" => StickDirection::ALL
+                .into_iter()
+                .find(|direction| format!("{:?}", direction) == name)
+                .map(Binding::Stick),
+            _ => None,
+        }
+        .ok_or_else(unknown)
+    }
+}
+
+/// the keys and buttons bound to each action in each layer, which players can change from the
+/// settings
+///
+/// actions missing from the saved settings, like ones added in an update, use their defaults, and
+/// layers with overlapping bindings are put back to their defaults
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredInputMap", into = "SavedInputMap")]
+pub struct InputMap {
+    layers: SavedInputMap,
+}
+
+type SavedInputMap = BTreeMap<InputLayer, BTreeMap<Action, Vec<Binding>>>;
+
+/// the controls as text, as they're read from the settings, so that a layer, action or binding
+/// that isn't known, like from an edited file or an older version, can be left out instead of
+/// failing to load all the settings
+type StoredInputMap = BTreeMap<String, BTreeMap<String, Vec<String>>>;
+
+/// a key or button bound to two actions that are checked at the same time
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    pub binding: Binding,
+    pub actions: (Action, Action),
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let layers = InputLayer::ALL
+            .iter()
+            .map(|layer| (*layer, Self::default_layer(*layer)))
+            .collect();
+        Self { layers }
+    }
+}
+
+impl From<SavedInputMap> for InputMap {
+    fn from(saved: SavedInputMap) -> Self {
+        let mut map = Self::default();
+        for (layer, bindings) in saved {
+            map.layers.entry(layer).or_default().extend(bindings);
+        }
+        for layer in InputLayer::ALL {
+            let missing_keys = Self::NEEDS_KEY
+                .iter()
+                .any(|(l, action)| *l == layer && !map.has_key(layer, *action));
+            if missing_keys || !map.conflicts(layer).is_empty() {
+                map.layers.insert(layer, Self::default_layer(layer));
+            }
+        }
+        map
+    }
+}
+
+impl From<StoredInputMap> for InputMap {
+    fn from(stored: StoredInputMap) -> Self {
+        let mut saved = SavedInputMap::new();
+        for (layer_name, actions) in stored {
+            let Some(layer) = from_name::<InputLayer>(&layer_name) else {
+                warn!("ignoring controls for unknown layer {}", layer_name);
+                continue;
+            };
+            for (action_name, bindings) in actions {
+                let Some(action) = from_name::<Action>(&action_name) else {
+                    warn!("ignoring controls for unknown action {}", action_name);
+                    continue;
+                };
+                let bindings: Vec<Binding> = bindings
+                    .into_iter()
+                    .filter_map(|text| {
+                        Binding::try_from(text)
+                            .inspect_err(|err| warn!("ignoring {}", err))
+                            .ok()
+                    })
+                    .collect();
+                // an action left without bindings keeps its defaults so it can still be used
+                if !bindings.is_empty() {
+                    saved.entry(layer).or_default().insert(action, bindings);
+                }
+            }
+        }
+        Self::from(saved)
+    }
+}
+
+/// the layer or action with the name it's saved under, like `reload_level`
+fn from_name<T: for<'de> Deserialize<'de>>(name: &str) -> Option<T> {
+    let deserializer: serde::de::value::StrDeserializer<serde::de::value::Error> =
+        name.into_deserializer();
+    T::deserialize(deserializer).ok()
+}
+
+impl From<InputMap> for SavedInputMap {
+    fn from(map: InputMap) -> Self {
+        map.layers
+    }
+}
+
+impl InputMap {
+    /// actions that always keep a key, so menus, including the controls screen, can be used with
+    /// only a keyboard
+    const NEEDS_KEY: [(InputLayer, Action); 2] = [
+        (InputLayer::Menu, Action::Confirm),
+        (InputLayer::Menu, Action::Cancel),
+    ];
+
+    fn has_key(&self, layer: InputLayer, action: Action) -> bool {
+        self.bindings(layer, action)
+            .iter()
+            .any(|b| matches!(b, Binding::Key(_)))
+    }
+
+    fn default_layer(layer: InputLayer) -> BTreeMap<Action, Vec<Binding>> {
+        layer
+            .actions()
+            .iter()
+            .map(|action| (*action, Self::default_bindings(*action)))
+            .collect()
+    }
+
+    fn default_bindings(action: Action) -> Vec<Binding> {
+        let stick = match action {
+            Action::Up => Some(StickDirection::Up),
+            Action::Down => Some(StickDirection::Down),
+            Action::Left => Some(StickDirection::Left),
+            Action::Right => Some(StickDirection::Right),
+            _ => None,
+        };
+        let (keys, button): (&[KeyCode], _) = match action {
+            Action::Up => (&[KeyCode::W, KeyCode::Up], Some(Button::DPadUp)),
+            Action::Down => (&[KeyCode::S, KeyCode::Down], Some(Button::DPadDown)),
+            Action::Left => (&[KeyCode::A, KeyCode::Left], Some(Button::DPadLeft)),
+            Action::Right => (&[KeyCode::D, KeyCode::Right], Some(Button::DPadRight)),
+            Action::Confirm => (&[KeyCode::J, KeyCode::Z], Some(Button::ActionDown)),
+            Action::Cancel => (&[KeyCode::K, KeyCode::X], Some(Button::ActionRight)),
+            Action::Reset => (&[KeyCode::L, KeyCode::C], Some(Button::ActionUp)),
+            Action::Rewind => (&[KeyCode::K, KeyCode::X], Some(Button::ActionLeft)),
+            Action::Pause => (
+                &[KeyCode::Escape, KeyCode::P],
+                Some(Button::RightCenterCluster),
+            ),
+            Action::ReloadLevel => (&[KeyCode::Key9], None),
+            Action::CycleTileset => (&[KeyCode::Key0], None),
+        };
+        keys.iter()
+            .copied()
+            .map(Binding::Key)
+            .chain(button.map(Binding::Button))
+            .chain(stick.map(Binding::Stick))
+            .collect()
+    }
+
+    pub fn bindings(&self, layer: InputLayer, action: Action) -> &[Binding] {
+        self.layers
+            .get(&layer)
+            .and_then(|bindings| bindings.get(&action))
+            .map_or(&[], |b| b.as_slice())
+    }
+
+    /// the keys bound to the action for hints on screen, like `J/Z`, or its other bindings when
+    /// it has no keys
+    pub fn hint(&self, layer: InputLayer, action: Action) -> String {
+        let bindings = self.bindings(layer, action);
+        let keys: Vec<String> = bindings
+            .iter()
+            .filter(|b| matches!(b, Binding::Key(_)))
+            .map(|b| b.label())
+            .collect();
+        if keys.is_empty() {
+            let labels: Vec<String> = bindings.iter().map(|b| b.label()).collect();
+            return labels.join("/");
+        }
+        keys.join("/")
+    }
+
+    /// the keys bound to the directions for hints on screen, grouping the first key of each, then
+    /// the second and so on, like `WASD / Arrow Keys`
+    pub fn directions_hint(&self, layer: InputLayer, directions: &[Action]) -> String {
+        let keys: Vec<Vec<KeyCode>> = directions
+            .iter()
+            .map(|action| {
+                self.bindings(layer, *action)
+                    .iter()
+                    .filter_map(|b| match b {
+                        Binding::Key(key) => Some(*key),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+        let groups = keys.iter().map(|k| k.len()).min().unwrap_or(0);
+        let arrows = [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right];
+        let hints: Vec<String> = (0..groups)
+            .map(|i| {
+                let group: Vec<KeyCode> = keys.iter().map(|k| k[i]).collect();
+                if group.iter().all(|key| arrows.contains(key)) {
+                    return "Arrow Keys".to_string();
+                }
+                let labels: Vec<String> =
+                    group.iter().map(|key| Binding::Key(*key).label()).collect();
+                if labels.iter().all(|label| label.chars().count() == 1) {
+                    labels.concat()
+                } else {
+                    labels.join("/")
+                }
+            })
+            .collect();
+        if hints.is_empty() {
+            return directions
+                .iter()
+                .map(|action| self.hint(layer, *action))
+                .collect::<Vec<String>>()
+                .join(", ");
+        }
+        hints.join(" / ")
+    }
+
+    /// the action in the layer, or one checked at the same time as it, that has the binding
+    fn action_for(&self, layer: InputLayer, binding: Binding) -> Option<Action> {
+        layer.active_with().iter().find_map(|layer| {
+            layer
+                .actions()
+                .iter()
+                .find(|action| self.bindings(*layer, **action).contains(&binding))
+                .copied()
+        })
+    }
+
+    /// bindings shared by two actions in the layer or the layers checked at the same time as it,
+    /// where only one of the actions would happen depending on which is checked first
+    pub fn conflicts(&self, layer: InputLayer) -> Vec<Conflict> {
+        let actions: Vec<(InputLayer, Action)> = layer
+            .active_with()
+            .iter()
+            .flat_map(|layer| layer.actions().iter().map(|action| (*layer, *action)))
+            .collect();
+        let mut conflicts = vec![];
+        for (i, (layer, action)) in actions.iter().enumerate() {
+            for (other_layer, other_action) in &actions[i + 1..] {
+                for binding in self.bindings(*layer, *action) {
+                    if self.bindings(*other_layer, *other_action).contains(binding) {
+                        conflicts.push(Conflict {
+                            binding: *binding,
+                            actions: (*action, *other_action),
+                        });
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// binds the key or button to the action, or unbinds it if it already was; errors when it's
+    /// the last binding, since the action couldn't be used anymore, or when another action
+    /// checked at the same time already uses it
+    pub fn toggle_binding(
+        &mut self,
+        layer: InputLayer,
+        action: Action,
+        binding: Binding,
+    ) -> Result<(), String> {
+        match self.action_for(layer, binding) {
+            Some(bound) if bound != action => {
+                return Err(format!(
+                    "{} is already used for {}",
+                    binding.label(),
+                    bound.name()
+                ))
+            }
+            _ => {}
+        }
+
+        let bindings = self
+            .layers
+            .entry(layer)
+            .or_default()
+            .entry(action)
+            .or_default();
+        let is_last_key = matches!(binding, Binding::Key(_))
+            && bindings
+                .iter()
+                .filter(|b| matches!(b, Binding::Key(_)))
+                .count()
+                == 1;
+        match bindings.iter().position(|b| *b == binding) {
+            Some(_) if bindings.len() == 1 => {
+                Err(format!("{} needs at least one binding", action.name()))
+            }
+            Some(_) if is_last_key && Self::NEEDS_KEY.contains(&(layer, action)) => Err(format!(
+                "{} needs at least one key so menus work without a gamepad",
+                action.name()
+            )),
+            Some(index) => {
+                bindings.remove(index);
+                Ok(())
+            }
+            None => {
+                bindings.push(binding);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn reset_action(&mut self, layer: InputLayer, action: Action) -> Result<(), String> {
+        let mut reset = self.clone();
+        reset
+            .layers
+            .entry(layer)
+            .or_default()
+            .insert(action, Self::default_bindings(action));
+        match reset.conflicts(layer).first() {
+            Some(conflict) => {
+                let (first, second) = conflict.actions;
+                let other = if first == action { second } else { first };
+                Err(format!(
+                    "{} is already used for {}",
+                    conflict.binding.label(),
+                    other.name()
+                ))
+            }
+            None => {
+                *self = reset;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// where each frame's actions come from
+enum InputSource {
+    /// the keyboard and gamepads
+    Live,
+    /// the keyboard and gamepads, written down as they're played
+    Recording(Recorder),
+    /// a recording, with the keyboard and gamepads ignored until it's over
+    Playback(Playback),
+}
+
+/// the actions for the current frame, read once at the start of it so a recording can stand in
+/// for the player
+pub struct Input {
+    source: InputSource,
+    frame: FrameInput,
+    /// the left stick, which is read each frame to know when it's first pushed
+    stick: Stick,
+    repeater: Repeater,
+    touch: TouchInput,
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self {
+            source: InputSource::Live,
+            frame: FrameInput::default(),
+            stick: Stick::default(),
+            repeater: Repeater::default(),
+            touch: TouchInput::default(),
+        }
+    }
+}
+
+impl Input {
+    /// records or replays the session when asked to from the command line
+    pub fn from_args(args: &crate::cli::Cli) -> Result<Self, String> {
+        let source = match (&args.record, &args.replay) {
+            (Some(path), _) => InputSource::Recording(Recorder::create(path)?),
+            (_, Some(path)) => InputSource::Playback(Playback::read(path)?),
+            _ => InputSource::Live,
+        };
+        Ok(Self {
+            source,
+            ..Default::default()
+        })
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.source, InputSource::Playback(_))
+    }
+
+    /// reads the frame's actions for every layer, returning a message for the player when a
+    /// replay finished or recording stopped, after which only the keyboard and gamepads are used
+    ///
+    /// the layer is the one last updated, whose buttons are shown for touch screens
+    pub fn begin_frame(
+        &mut self,
+        dt: f32,
+        settings: &Settings,
+        gamepads: &Gamepads,
+        layer: InputLayer,
+    ) -> Option<String> {
+        let mut message = None;
+        let replayed = match &mut self.source {
+            InputSource::Playback(playback) => playback.next_frame(),
+            _ => None,
+        };
+        if let Some(frame) = replayed {
+            self.frame = frame;
+        } else {
+            if self.is_replaying() {
+                self.source = InputSource::Live;
+                message = Some("Replay finished".to_string());
+            }
+
+            self.stick.update(gamepads, settings.stick_deadzone());
+            let gestures: Vec<Action> = self
+                .touch
+                .update(&touches(), |p| {
+                    touch::to_virtual(p, screen_width(), screen_height())
+                })
+                .iter()
+                .map(|gesture| gesture.action(layer))
+                .collect();
+            self.frame = self.read_live(dt, settings.controls(), gamepads, &gestures);
+            if let InputSource::Recording(recorder) = &mut self.source {
+                if let Err(err) = recorder.record(&self.frame) {
+                    message = Some(format!("Stopped recording: {}", err));
+                    self.source = InputSource::Live;
+                }
+            }
+        }
+
+        self.repeater.update(&self.frame, settings.key_repeat());
+        message
+    }
+
+    /// the actions from the keyboard, gamepads and the gestures finished this frame, which
+    /// happen in every layer that has them, along with the text typed and the key or button
+    /// pressed
+    fn read_live(
+        &self,
+        dt: f32,
+        controls: &InputMap,
+        gamepads: &Gamepads,
+        gestures: &[Action],
+    ) -> FrameInput {
+        // the queue is popped from the end, so chars typed within the same frame come out reversed
+        let mut typed = vec![];
+        while let Some(c) = get_char_pressed() {
+            typed.push(c);
+        }
+        let mut frame = FrameInput {
+            dt,
+            typed: typed.into_iter().rev().collect(),
+            binding: Binding::just_pressed(gamepads, &self.stick),
+            ..Default::default()
+        };
+        for layer in InputLayer::ALL {
+            for action in layer.actions() {
+                let bindings = controls.bindings(layer, *action);
+                let gesture = gestures.contains(action);
+                if gesture || bindings.iter().any(|b| b.is_pressed(gamepads, &self.stick)) {
+                    frame.pressed.push((layer, *action));
+                }
+                if gesture || bindings.iter().any(|b| b.is_down(gamepads, &self.stick)) {
+                    frame.down.push((layer, *action));
+                }
+            }
+        }
+        frame
+    }
+
+    /// text typed this frame, like for a profile name
+    pub fn typed(&self) -> &str {
+        &self.frame.typed
+    }
+
+    /// the key, gamepad button or stick direction pressed this frame, whatever action it's bound
+    /// to, like when rebinding an action
+    pub fn binding_pressed(&self) -> Option<Binding> {
+        self.frame.binding
+    }
+
+    pub fn touch(&self) -> &TouchInput {
+        &self.touch
+    }
+
+    /// seconds since the last frame, which comes from the recording when replaying so that
+    /// timing, like how long a key is held, plays out the same
+    pub fn frame_time(&self) -> f32 {
+        self.frame.dt
+    }
+}
+
+/// just pressed, not held down, in the input layer of the scene being updated
+pub fn action_pressed(action: Action, ctx: &Context) -> bool {
+    layer_for(action, ctx).is_some_and(|layer| ctx.input.frame.is_pressed(layer, action))
+}
+
+/// held down for multiple frames, in the input layer of the scene being updated
+pub fn action_down(action: Action, ctx: &Context) -> bool {
+    layer_for(action, ctx).is_some_and(|layer| ctx.input.frame.is_down(layer, action))
+}
+
+/// pressed, or held down long enough to repeat, in the input layer of the scene being updated;
+/// used for moving around, where holding a direction keeps going
+pub fn action_repeated(action: Action, ctx: &Context) -> bool {
+    layer_for(action, ctx).is_some_and(|layer| ctx.input.repeater.is_repeated(layer, action))
+}
+
+fn layer_for(action: Action, ctx: &Context) -> Option<InputLayer> {
+    ctx.input_layer.layer_for(action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binding_text() {
+        for binding in [
+            Binding::Key(KeyCode::Key1),
+            Binding::Button(Button::DPadUp),
+            Binding::Stick(StickDirection::Left),
+        ] {
+            let text = String::from(binding);
+            assert_eq!(Binding::try_from(text), Ok(binding));
+        }
+        assert_eq!(Binding::Key(KeyCode::Key1).label(), "1");
+        assert_eq!(Binding::Stick(StickDirection::Up).label(), "Stick Up");
+        assert!(Binding::try_from("key:Unknown".to_string()).is_err());
+        assert!(Binding::try_from("W".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_toggle_binding() {
+        let mut map = InputMap::default();
+        let up = |map: &InputMap| map.bindings(InputLayer::Menu, Action::Up).to_vec();
+        assert!(map
+            .toggle_binding(InputLayer::Menu, Action::Up, Binding::Key(KeyCode::I))
+            .is_ok());
+        assert_eq!(up(&map).len(), 5);
+
+        for binding in [
+            Binding::Key(KeyCode::I),
+            Binding::Key(KeyCode::W),
+            Binding::Key(KeyCode::Up),
+            Binding::Stick(StickDirection::Up),
+        ] {
+            assert!(map
+                .toggle_binding(InputLayer::Menu, Action::Up, binding)
+                .is_ok());
+        }
+        // the last binding stays so the action can still be used
+        assert!(map
+            .toggle_binding(
+                InputLayer::Menu,
+                Action::Up,
+                Binding::Button(Button::DPadUp)
+            )
+            .is_err());
+        assert_eq!(up(&map), [Binding::Button(Button::DPadUp)]);
+        // each layer has its own bindings
+        assert_eq!(map.bindings(InputLayer::Gameplay, Action::Up).len(), 4);
+
+        assert!(map.reset_action(InputLayer::Menu, Action::Up).is_ok());
+        assert_eq!(map, InputMap::default());
+    }
+
+    #[test]
+    fn test_default_layers_have_no_conflicts() {
+        let map = InputMap::default();
+        for layer in InputLayer::ALL {
+            assert_eq!(map.conflicts(layer), vec![]);
+        }
+        // the same keys mean different things in different layers
+        let k = Binding::Key(KeyCode::K);
+        assert_eq!(
+            map.action_for(InputLayer::Gameplay, k),
+            Some(Action::Rewind)
+        );
+        assert_eq!(map.action_for(InputLayer::Menu, k), Some(Action::Cancel));
+    }
+
+    #[test]
+    fn test_rejects_conflicts() {
+        let mut map = InputMap::default();
+        let k = Binding::Key(KeyCode::K);
+        assert_eq!(
+            map.toggle_binding(InputLayer::Gameplay, Action::Confirm, k),
+            Err("K is already used for Rewind".to_string())
+        );
+        // the editor is checked while playing, so it can't use gameplay keys either
+        assert!(map
+            .toggle_binding(InputLayer::Editor, Action::ReloadLevel, k)
+            .is_err());
+        // but menus are checked on their own
+        assert!(map
+            .toggle_binding(InputLayer::Menu, Action::Reset, Binding::Key(KeyCode::Key9))
+            .is_ok());
+
+        // putting an action back to its defaults can't cause one either
+        let w = Binding::Key(KeyCode::W);
+        map.toggle_binding(InputLayer::Gameplay, Action::Up, w)
+            .unwrap();
+        map.toggle_binding(InputLayer::Gameplay, Action::Pause, w)
+            .unwrap();
+        assert_eq!(
+            map.reset_action(InputLayer::Gameplay, Action::Up),
+            Err("W is already used for Pause".to_string())
+        );
+    }
+
+    #[test]
+    fn test_hints() {
+        let mut map = InputMap::default();
+        let directions = [Action::Up, Action::Left, Action::Down, Action::Right];
+        assert_eq!(map.hint(InputLayer::Menu, Action::Confirm), "J/Z");
+        assert_eq!(
+            map.directions_hint(InputLayer::Gameplay, &directions),
+            "WASD / Arrow Keys"
+        );
+
+        map.toggle_binding(InputLayer::Gameplay, Action::Up, Binding::Key(KeyCode::W))
+            .unwrap();
+        map.toggle_binding(InputLayer::Gameplay, Action::Up, Binding::Key(KeyCode::I))
+            .unwrap();
+        assert_eq!(
+            map.directions_hint(InputLayer::Gameplay, &directions),
+            "Up/A/S/D / I/Left/Down/Right"
+        );
+        map.toggle_binding(
+            InputLayer::Gameplay,
+            Action::Rewind,
+            Binding::Key(KeyCode::K),
+        )
+        .unwrap();
+        map.toggle_binding(
+            InputLayer::Gameplay,
+            Action::Rewind,
+            Binding::Key(KeyCode::X),
+        )
+        .unwrap();
+        assert_eq!(
+            map.hint(InputLayer::Gameplay, Action::Rewind),
+            "Pad ActionLeft"
+        );
+    }
+
+    #[test]
+    fn test_menus_keep_a_key() {
+        let mut map = InputMap::default();
+        let layer = InputLayer::Menu;
+        map.toggle_binding(layer, Action::Confirm, Binding::Key(KeyCode::J))
+            .unwrap();
+        assert_eq!(
+            map.toggle_binding(layer, Action::Confirm, Binding::Key(KeyCode::Z)),
+            Err("Confirm needs at least one key so menus work without a gamepad".to_string())
+        );
+        // gamepad buttons can still be taken away
+        map.toggle_binding(layer, Action::Confirm, Binding::Button(Button::ActionDown))
+            .unwrap();
+        // but not in other layers
+        map.toggle_binding(
+            InputLayer::Gameplay,
+            Action::Confirm,
+            Binding::Key(KeyCode::J),
+        )
+        .unwrap();
+        map.toggle_binding(
+            InputLayer::Gameplay,
+            Action::Confirm,
+            Binding::Key(KeyCode::Z),
+        )
+        .unwrap();
+
+        let mut saved = SavedInputMap::default();
+        saved
+            .entry(layer)
+            .or_default()
+            .insert(Action::Cancel, vec![Binding::Button(Button::ActionRight)]);
+        assert_eq!(InputMap::from(saved), InputMap::default());
+    }
+
+    #[test]
+    fn test_saved_conflicts_use_defaults() {
+        let mut saved = SavedInputMap::default();
+        saved
+            .entry(InputLayer::Menu)
+            .or_default()
+            .insert(Action::Confirm, vec![Binding::Key(KeyCode::X)]);
+        assert_eq!(InputMap::from(saved), InputMap::default());
     }
 }
@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Action, Binding, InputLayer};
+
+/// the actions held and just pressed in one frame in each input layer, along with how long the
+/// frame took, the text typed and the key or button pressed, which is everything the game reads
+/// from the player
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameInput {
+    /// seconds since the last frame
+    pub dt: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pressed: Vec<(InputLayer, Action)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub down: Vec<(InputLayer, Action)>,
+    /// text typed this frame, in the order it was typed
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub typed: String,
+    /// the key, gamepad button or stick direction pressed this frame, whatever it's bound to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binding: Option<Binding>,
+}
+
+impl FrameInput {
+    pub fn is_pressed(&self, layer: InputLayer, action: Action) -> bool {
+        self.pressed.contains(&(layer, action))
+    }
+
+    pub fn is_down(&self, layer: InputLayer, action: Action) -> bool {
+        self.down.contains(&(layer, action))
+    }
+}
+
+/// writes each frame's input as a line of JSON as it's played, so the session can be replayed
+/// even if the game crashes
+pub struct Recorder {
+    out: Box<dyn Write>,
+}
+
+impl Recorder {
+    pub fn new(out: impl Write + 'static) -> Self {
+        Self { out: Box::new(out) }
+    }
+
+    /// records to the file, replacing it if it's already there
+    pub fn create(path: &std::path::Path) -> Result<Self, String> {
+        let file = std::fs::File::create(path)
+            .map_err(|e| format!("couldn't create {}: {}", path.display(), e))?;
+        Ok(Self::new(std::io::LineWriter::new(file)))
+    }
+
+    pub fn record(&mut self, frame: &FrameInput) -> Result<(), String> {
+        let line = serde_json::to_string(frame).map_err(|e| e.to_string())?;
+        writeln!(self.out, "{}", line).map_err(|e| e.to_string())
+    }
+}
+
+/// the frames of a recording, fed back in one at a time in place of the player's input
+#[derive(Debug)]
+pub struct Playback {
+    frames: VecDeque<FrameInput>,
+}
+
+impl Playback {
+    /// reads a recording written by `Recorder`, one frame per line
+    pub fn parse(recording: &str) -> Result<Self, String> {
+        let frames = recording
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|e| format!("line {}: {}", i + 1, e))
+            })
+            .collect::<Result<VecDeque<FrameInput>, String>>()?;
+        Ok(Self { frames })
+    }
+
+    pub fn read(path: &std::path::Path) -> Result<Self, String> {
+        let recording = std::fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+        Self::parse(&recording).map_err(|e| format!("{} is not a recording, {}", path.display(), e))
+    }
+
+    /// the next frame's input, or none once the recording is over
+    pub fn next_frame(&mut self) -> Option<FrameInput> {
+        self.frames.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// collects what's written so it can be read back after the recorder has it
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_replays_recorded_frames() {
+        let frames = vec![
+            FrameInput {
+                dt: 0.5,
+                ..Default::default()
+            },
+            FrameInput {
+                dt: 0.25,
+                pressed: vec![(InputLayer::Menu, Action::Confirm)],
+                down: vec![(InputLayer::Gameplay, Action::Up)],
+                ..Default::default()
+            },
+            FrameInput {
+                dt: 0.25,
+                typed: "ab".to_string(),
+                binding: Some(Binding::Key(macroquad::input::KeyCode::B)),
+                ..Default::default()
+            },
+        ];
+        let buffer = SharedBuffer::default();
+        let mut recorder = Recorder::new(buffer.clone());
+        for frame in &frames {
+            recorder.record(frame).unwrap();
+        }
+
+        let recording = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert_eq!(recording.lines().next(), Some(r#"{"dt":0.5}"#));
+
+        let mut playback = Playback::parse(&recording).unwrap();
+        assert_eq!(playback.next_frame().as_ref(), frames.first());
+        let frame = playback.next_frame().unwrap();
+        assert!(frame.is_pressed(InputLayer::Menu, Action::Confirm));
+        assert!(!frame.is_pressed(InputLayer::Gameplay, Action::Confirm));
+        assert!(frame.is_down(InputLayer::Gameplay, Action::Up));
+        assert_eq!(playback.next_frame().as_ref(), frames.last());
+        assert_eq!(playback.next_frame(), None);
+    }
+
+    #[test]
+    fn test_bad_recording() {
+        let err = Playback::parse("{\"dt\":0.5}\n{\"dt\":\"soon\"}\n").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{}", err);
+    }
+}
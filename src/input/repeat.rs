@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::recording::FrameInput;
+use super::{Action, InputLayer};
+
+/// the shortest time between repeats that acceleration can get down to
+const MIN_REPEAT_INTERVAL: f32 = 0.04;
+
+/// how much acceleration shortens the time between repeats each time, when turned on
+const DEFAULT_ACCELERATION: f32 = 0.1;
+
+/// how an action repeats while it's held down, like moving through a menu or across a level
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyRepeat {
+    /// seconds after pressing before the first repeat
+    pub delay: f32,
+    /// seconds between repeats after that
+    pub interval: f32,
+    /// how much shorter each repeat's interval is than the one before, from 0 for a steady pace
+    /// up to 1
+    pub acceleration: f32,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+impl KeyRepeat {
+    pub const SLOW: KeyRepeat = KeyRepeat {
+        delay: 0.35,
+        interval: 0.25,
+        acceleration: 0.,
+    };
+    pub const NORMAL: KeyRepeat = KeyRepeat {
+        delay: 0.2,
+        interval: 0.2,
+        acceleration: 0.,
+    };
+    pub const FAST: KeyRepeat = KeyRepeat {
+        delay: 0.15,
+        interval: 0.1,
+        acceleration: 0.,
+    };
+    const PRESETS: [(&'static str, KeyRepeat); 3] = [
+        ("Slow", KeyRepeat::SLOW),
+        ("Normal", KeyRepeat::NORMAL),
+        ("Fast", KeyRepeat::FAST),
+    ];
+
+    /// the name of the preset with the same delay and interval, or custom when they were set by
+    /// hand in the settings file
+    pub fn preset_name(&self) -> &'static str {
+        Self::PRESETS
+            .iter()
+            .find(|(_, preset)| preset.delay == self.delay && preset.interval == self.interval)
+            .map_or("Custom", |(name, _)| name)
+    }
+
+    /// the timing of the next preset, keeping the acceleration
+    pub fn next_preset(&self) -> Self {
+        let index = Self::PRESETS
+            .iter()
+            .position(|(_, preset)| preset.delay == self.delay && preset.interval == self.interval)
+            .map_or(1, |i| (i + 1) % Self::PRESETS.len());
+        Self {
+            acceleration: self.acceleration,
+            ..Self::PRESETS[index].1
+        }
+    }
+
+    pub fn is_accelerating(&self) -> bool {
+        self.acceleration > 0.
+    }
+
+    pub fn toggle_acceleration(&self) -> Self {
+        Self {
+            acceleration: if self.is_accelerating() {
+                0.
+            } else {
+                DEFAULT_ACCELERATION
+            },
+            ..*self
+        }
+    }
+
+    /// the timing for the action; rewinding goes twice as fast since it's held to undo a lot of
+    /// moves at once
+    fn for_action(&self, action: Action) -> Self {
+        match action {
+            Action::Rewind => Self {
+                delay: self.delay / 2.,
+                interval: self.interval / 2.,
+                ..*self
+            },
+            _ => *self,
+        }
+    }
+
+    /// seconds from one repeat to the next after the action repeated this many times
+    fn interval_after(&self, repeats: u32) -> f32 {
+        let speed_up = (1. - self.acceleration.clamp(0., 1.)).powi(repeats as i32);
+        (self.interval * speed_up).max(MIN_REPEAT_INTERVAL.min(self.interval))
+    }
+}
+
+/// how long an action has been held
+#[derive(Debug)]
+struct Held {
+    time: f32,
+    /// how long it has to be held for the next repeat
+    next_repeat: f32,
+    repeats: u32,
+}
+
+/// keeps track of the actions held down in every layer, to know which ones happen this frame
+#[derive(Debug, Default)]
+pub struct Repeater {
+    held: BTreeMap<(InputLayer, Action), Held>,
+    /// the actions that were pressed or repeated this frame
+    repeated: Vec<(InputLayer, Action)>,
+}
+
+impl Repeater {
+    pub fn update(&mut self, frame: &FrameInput, timing: &KeyRepeat) {
+        self.repeated.clear();
+        self.held.retain(|key, _| frame.down.contains(key));
+
+        for key in &frame.pressed {
+            self.held.insert(
+                *key,
+                Held {
+                    time: 0.,
+                    next_repeat: timing.for_action(key.1).delay,
+                    repeats: 0,
+                },
+            );
+            self.repeated.push(*key);
+        }
+
+        for key in &frame.down {
+            let timing = timing.for_action(key.1);
+            // something held since before it could be pressed, like a key held while the game
+            // opened, waits out the delay rather than repeating right away
+            let held = self.held.entry(*key).or_insert(Held {
+                time: 0.,
+                next_repeat: timing.delay,
+                repeats: 0,
+            });
+            if frame.pressed.contains(key) {
+                continue;
+            }
+            held.time += frame.dt;
+            if held.time >= held.next_repeat {
+                held.repeats += 1;
+                held.next_repeat += timing.interval_after(held.repeats);
+                self.repeated.push(*key);
+            }
+        }
+    }
+
+    /// pressed this frame, or held long enough that it repeats this frame
+    pub fn is_repeated(&self, layer: InputLayer, action: Action) -> bool {
+        self.repeated.contains(&(layer, action))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UP: (InputLayer, Action) = (InputLayer::Menu, Action::Up);
+
+    /// runs frames of the given length with up held, returning which ones it repeated on
+    fn hold_up(repeater: &mut Repeater, timing: &KeyRepeat, dt: f32, frames: usize) -> Vec<usize> {
+        (0..frames)
+            .filter(|i| {
+                let frame = FrameInput {
+                    dt,
+                    pressed: if *i == 0 { vec![UP] } else { vec![] },
+                    down: vec![UP],
+                    ..Default::default()
+                };
+                repeater.update(&frame, timing);
+                repeater.is_repeated(UP.0, UP.1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_repeats_after_delay() {
+        let timing = KeyRepeat {
+            delay: 0.375,
+            interval: 0.25,
+            acceleration: 0.,
+        };
+        let mut repeater = Repeater::default();
+        assert_eq!(hold_up(&mut repeater, &timing, 0.125, 8), vec![0, 3, 5, 7]);
+
+        // letting go and pressing again starts over
+        repeater.update(&FrameInput::default(), &timing);
+        assert_eq!(hold_up(&mut repeater, &timing, 0.125, 4), vec![0, 3]);
+    }
+
+    #[test]
+    fn test_acceleration() {
+        let timing = KeyRepeat {
+            delay: 0.125,
+            interval: 0.5,
+            acceleration: 0.5,
+        };
+        let mut repeater = Repeater::default();
+        // intervals of 0.25, 0.125, 0.0625, then as short as they go
+        assert_eq!(
+            hold_up(&mut repeater, &timing, 0.0625, 11),
+            vec![0, 2, 6, 8, 9, 10]
+        );
+    }
+
+    #[test]
+    fn test_rewind_is_faster() {
+        let timing = KeyRepeat::NORMAL.for_action(Action::Rewind);
+        assert_eq!(timing.delay, 0.1);
+        assert_eq!(timing.interval, 0.1);
+    }
+
+    #[test]
+    fn test_presets() {
+        assert_eq!(KeyRepeat::default().preset_name(), "Normal");
+        let accelerating = KeyRepeat::default().toggle_acceleration();
+        assert_eq!(accelerating.preset_name(), "Normal");
+        let fast = accelerating.next_preset();
+        assert_eq!(fast.preset_name(), "Fast");
+        assert!(fast.is_accelerating());
+        assert_eq!(fast.next_preset().preset_name(), "Slow");
+
+        let custom = KeyRepeat {
+            delay: 1.,
+            ..KeyRepeat::default()
+        };
+        assert_eq!(custom.preset_name(), "Custom");
+        assert_eq!(custom.next_preset().preset_name(), "Normal");
+    }
+}
@@ -0,0 +1,130 @@
+use gamepads::Gamepads;
+
+/// how far the left stick has to be pushed, from 0 to 1, for it to count as a direction
+pub const DEFAULT_STICK_DEADZONE: f32 = 0.5;
+
+/// a way the left stick of a gamepad can be pushed, which works like a d-pad button
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StickDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl StickDirection {
+    pub const ALL: [StickDirection; 4] = [
+        StickDirection::Up,
+        StickDirection::Down,
+        StickDirection::Left,
+        StickDirection::Right,
+    ];
+
+    /// the direction the stick is pushed furthest in, or none while it's within the deadzone
+    ///
+    /// the axes go from -1 to 1, with negative being left and down
+    pub fn from_axes(x: f32, y: f32, deadzone: f32) -> Option<Self> {
+        if x.hypot(y) < deadzone {
+            return None;
+        }
+        Some(if x.abs() > y.abs() {
+            if x > 0. {
+                StickDirection::Right
+            } else {
+                StickDirection::Left
+            }
+        } else if y > 0. {
+            StickDirection::Up
+        } else {
+            StickDirection::Down
+        })
+    }
+}
+
+/// the direction of the left stick this frame and the last one, so pushing it can be told apart
+/// from holding it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stick {
+    direction: Option<StickDirection>,
+    last_direction: Option<StickDirection>,
+}
+
+impl Stick {
+    /// moves on to the next frame with the direction of the first connected gamepad's stick
+    /// that's pushed
+    pub fn update(&mut self, gamepads: &Gamepads, deadzone: f32) {
+        let direction = gamepads.all().find_map(|g| {
+            let (x, y) = g.left_stick();
+            StickDirection::from_axes(x, y, deadzone)
+        });
+        self.set_direction(direction);
+    }
+
+    fn set_direction(&mut self, direction: Option<StickDirection>) {
+        self.last_direction = self.direction;
+        self.direction = direction;
+    }
+
+    /// pushed into the direction this frame, like a button that was just pressed
+    pub fn is_pushed(&self, direction: StickDirection) -> bool {
+        self.is_held(direction) && self.last_direction != Some(direction)
+    }
+
+    /// pushed into the direction, for as many frames as it's held there
+    pub fn is_held(&self, direction: StickDirection) -> bool {
+        self.direction == Some(direction)
+    }
+
+    /// the direction pushed this frame, if any
+    pub fn just_pushed(&self) -> Option<StickDirection> {
+        self.direction.filter(|d| self.is_pushed(*d))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direction_from_axes() {
+        assert_eq!(StickDirection::from_axes(0.3, 0.2, 0.5), None);
+        assert_eq!(
+            StickDirection::from_axes(0.3, 0.2, 0.3),
+            Some(StickDirection::Right)
+        );
+        assert_eq!(
+            StickDirection::from_axes(-0.2, -0.9, 0.5),
+            Some(StickDirection::Down)
+        );
+        assert_eq!(
+            StickDirection::from_axes(0.1, 0.6, 0.5),
+            Some(StickDirection::Up)
+        );
+        assert_eq!(
+            StickDirection::from_axes(-0.7, 0.2, 0.5),
+            Some(StickDirection::Left)
+        );
+    }
+
+    #[test]
+    fn test_pushed_only_on_the_first_frame() {
+        let mut stick = Stick::default();
+        stick.set_direction(Some(StickDirection::Up));
+        assert!(stick.is_pushed(StickDirection::Up));
+        assert_eq!(stick.just_pushed(), Some(StickDirection::Up));
+
+        stick.set_direction(Some(StickDirection::Up));
+        assert!(!stick.is_pushed(StickDirection::Up));
+        assert!(stick.is_held(StickDirection::Up));
+        assert_eq!(stick.just_pushed(), None);
+
+        // rolling the stick over to another direction pushes it without going back to the middle
+        stick.set_direction(Some(StickDirection::Left));
+        assert!(stick.is_pushed(StickDirection::Left));
+        assert!(!stick.is_held(StickDirection::Up));
+
+        stick.set_direction(None);
+        stick.set_direction(Some(StickDirection::Left));
+        assert!(stick.is_pushed(StickDirection::Left));
+    }
+}
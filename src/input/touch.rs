@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use macroquad::color::{Color, WHITE};
+use macroquad::input::{Touch, TouchPhase};
+use macroquad::math::{vec2, Rect, Vec2};
+use macroquad::shapes::draw_rectangle;
+
+use super::{Action, InputLayer};
+use crate::color::DARKGRAY;
+use crate::consts::{VIRTUAL_HEIGHT, VIRTUAL_WIDTH};
+use crate::context::Context;
+use crate::text::{draw_text, Size};
+
+/// how far a finger has to move, in virtual pixels, for it to be a swipe rather than a tap
+const SWIPE_MIN_DISTANCE: f32 = 60.;
+const BUTTON_WIDTH: f32 = 160.;
+const BUTTON_HEIGHT: f32 = 64.;
+const BUTTON_MARGIN: f32 = 16.;
+
+/// what a finger did between touching the screen and letting go
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    Swipe(Action),
+    /// where the finger was lifted, in virtual pixels
+    Tap(Vec2),
+}
+
+impl Gesture {
+    /// a swipe in the direction the finger moved furthest in, or a tap if it barely moved
+    pub fn from_positions(start: Vec2, end: Vec2) -> Self {
+        let delta = end - start;
+        if delta.length() < SWIPE_MIN_DISTANCE {
+            return Gesture::Tap(end);
+        }
+        // screen coordinates go down, unlike the stick
+        Gesture::Swipe(if delta.x.abs() > delta.y.abs() {
+            if delta.x > 0. {
+                Action::Right
+            } else {
+                Action::Left
+            }
+        } else if delta.y > 0. {
+            Action::Down
+        } else {
+            Action::Up
+        })
+    }
+
+    /// the action for the gesture in the layer, where tapping a button on the bar does what it
+    /// says and tapping anywhere else confirms
+    pub fn action(&self, layer: InputLayer) -> Action {
+        match self {
+            Gesture::Swipe(action) => *action,
+            Gesture::Tap(position) => button_bar(layer)
+                .into_iter()
+                .find(|(rect, _)| rect.contains(*position))
+                .map_or(Action::Confirm, |(_, action)| action),
+        }
+    }
+}
+
+/// the on-screen buttons along the bottom for the actions there's no gesture for
+fn button_bar(layer: InputLayer) -> Vec<(Rect, Action)> {
+    let actions: &[Action] = match layer {
+        InputLayer::Gameplay | InputLayer::Editor => {
+            &[Action::Rewind, Action::Reset, Action::Pause]
+        }
+        InputLayer::Menu => &[Action::Cancel],
+    };
+    let y = VIRTUAL_HEIGHT - BUTTON_HEIGHT - BUTTON_MARGIN;
+    actions
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, action)| {
+            let x = VIRTUAL_WIDTH - (BUTTON_WIDTH + BUTTON_MARGIN) * (i + 1) as f32;
+            (Rect::new(x, y, BUTTON_WIDTH, BUTTON_HEIGHT), *action)
+        })
+        .collect()
+}
+
+/// converts a position on the window to one on the letterboxed virtual screen
+pub fn to_virtual(position: Vec2, screen_width: f32, screen_height: f32) -> Vec2 {
+    let scale = f32::min(screen_width / VIRTUAL_WIDTH, screen_height / VIRTUAL_HEIGHT);
+    let offset = vec2(
+        (screen_width - VIRTUAL_WIDTH * scale) * 0.5,
+        (screen_height - VIRTUAL_HEIGHT * scale) * 0.5,
+    );
+    (position - offset) / scale
+}
+
+/// follows fingers on a touch screen to turn them into gestures once they're lifted
+#[derive(Debug, Default)]
+pub struct TouchInput {
+    /// where each finger on the screen started, in virtual pixels
+    starts: HashMap<u64, Vec2>,
+    /// whether the screen was ever touched, so the button bar is only shown on touch screens
+    used: bool,
+}
+
+impl TouchInput {
+    /// the gestures finished this frame, with positions already converted to virtual pixels
+    pub fn update(&mut self, touches: &[Touch], to_virtual: impl Fn(Vec2) -> Vec2) -> Vec<Gesture> {
+        let mut gestures = vec![];
+        for touch in touches {
+            let position = to_virtual(touch.position);
+            match touch.phase {
+                TouchPhase::Started => {
+                    self.used = true;
+                    self.starts.insert(touch.id, position);
+                }
+                TouchPhase::Ended => {
+                    if let Some(start) = self.starts.remove(&touch.id) {
+                        gestures.push(Gesture::from_positions(start, position));
+                    }
+                }
+                TouchPhase::Cancelled => {
+                    self.starts.remove(&touch.id);
+                }
+                TouchPhase::Moved | TouchPhase::Stationary => {}
+            }
+        }
+        gestures
+    }
+
+    pub fn is_used(&self) -> bool {
+        self.used
+    }
+}
+
+/// draws the buttons for the current input layer once the screen has been touched
+pub fn draw_button_bar(ctx: &mut Context) {
+    if !ctx.input.touch().is_used() {
+        return;
+    }
+
+    for (rect, action) in button_bar(ctx.input_layer) {
+        draw_rectangle(
+            rect.x,
+            rect.y,
+            rect.w,
+            rect.h,
+            Color {
+                a: 0.75,
+                ..DARKGRAY
+            },
+        );
+        draw_text(
+            ctx,
+            action.name(),
+            rect.x + 16.,
+            rect.y + 40.,
+            Size::Small,
+            WHITE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: u64, phase: TouchPhase, x: f32, y: f32) -> Touch {
+        Touch {
+            id,
+            phase,
+            position: vec2(x, y),
+        }
+    }
+
+    #[test]
+    fn test_gestures() {
+        let start = vec2(400., 300.);
+        assert_eq!(
+            Gesture::from_positions(start, vec2(500., 320.)),
+            Gesture::Swipe(Action::Right)
+        );
+        assert_eq!(
+            Gesture::from_positions(start, vec2(380., 200.)),
+            Gesture::Swipe(Action::Up)
+        );
+        assert_eq!(
+            Gesture::from_positions(start, vec2(410., 310.)),
+            Gesture::Tap(vec2(410., 310.))
+        );
+    }
+
+    #[test]
+    fn test_tap_actions() {
+        let middle = Gesture::Tap(vec2(VIRTUAL_WIDTH / 2., VIRTUAL_HEIGHT / 2.));
+        assert_eq!(middle.action(InputLayer::Gameplay), Action::Confirm);
+
+        // the last button is in the bottom right corner
+        let corner = Gesture::Tap(vec2(VIRTUAL_WIDTH - 20., VIRTUAL_HEIGHT - 20.));
+        assert_eq!(corner.action(InputLayer::Gameplay), Action::Pause);
+        assert_eq!(corner.action(InputLayer::Menu), Action::Cancel);
+    }
+
+    #[test]
+    fn test_follows_fingers() {
+        let mut input = TouchInput::default();
+        assert!(!input.is_used());
+        let same = |p: Vec2| p;
+
+        let started = [touch(1, TouchPhase::Started, 100., 100.)];
+        assert!(input.update(&started, same).is_empty());
+        assert!(input.is_used());
+
+        // a second finger that's cancelled doesn't count
+        let moved = [
+            touch(1, TouchPhase::Moved, 100., 200.),
+            touch(2, TouchPhase::Started, 600., 600.),
+        ];
+        assert!(input.update(&moved, same).is_empty());
+        let ended = [
+            touch(1, TouchPhase::Ended, 100., 220.),
+            touch(2, TouchPhase::Cancelled, 600., 600.),
+        ];
+        assert_eq!(
+            input.update(&ended, same),
+            vec![Gesture::Swipe(Action::Down)]
+        );
+    }
+
+    #[test]
+    fn test_to_virtual() {
+        // twice as wide as the virtual screen is tall, so it's letterboxed on the sides
+        let position = to_virtual(vec2(1280., 720.), 2560., 720.);
+        assert_eq!(position, vec2(640., 720.));
+        let position = to_virtual(vec2(1280., 720.), 2560., 1440.);
+        assert_eq!(position, vec2(640., 360.));
+    }
+}
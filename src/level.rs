@@ -8,6 +8,7 @@ use self::pack::PackLevel;
 
 pub mod pack;
 pub mod playable_level;
+pub mod solver;
 
 #[derive(Debug, Clone)]
 pub struct Level {
@@ -23,8 +24,8 @@ pub struct Level {
 
 impl Level {
     /// Parses a level from the PackLevel data
-    /// panics if there's an unexpected char in the level data
-    pub fn parse(pack_level: &PackLevel) -> Result<Self, macroquad::Error> {
+    /// errors if there's an unexpected char in the level data
+    pub fn parse(pack_level: &PackLevel) -> Result<Self, String> {
         let rows = pack_level.data.lines();
         let mut walls = vec![];
         let mut crates = vec![];
@@ -77,7 +78,14 @@ impl Level {
                             grounds.push(pos)
                         }
                     }
-                    _ => panic!("unexpected char in level at {}, {}", x, y),
+                    _ => {
+                        return Err(format!(
+                            "unexpected char '{}' at row {}, column {}",
+                            c,
+                            y + 1,
+                            x + 1
+                        ))
+                    }
                 }
             }
         }
@@ -142,6 +150,8 @@ mod tests {
             #####
             "#
             .to_string(),
+            par_moves: None,
+            par_pushes: None,
         };
         let level = Level::parse(&pack_level).unwrap();
         assert!(level.is_valid());
@@ -157,6 +167,8 @@ mod tests {
             ###
             "#
             .to_string(),
+            par_moves: None,
+            par_pushes: None,
         };
         let level = Level::parse(&pack_level).unwrap();
         assert!(!level.is_valid());
@@ -172,6 +184,8 @@ mod tests {
             #####
             "#
             .to_string(),
+            par_moves: None,
+            par_pushes: None,
         };
         let level = Level::parse(&pack_level).unwrap();
         assert!(!level.is_valid());
@@ -187,6 +201,8 @@ mod tests {
             #######
             "#
             .to_string(),
+            par_moves: None,
+            par_pushes: None,
         };
         let level = Level::parse(&pack_level).unwrap();
         assert!(!level.is_valid());
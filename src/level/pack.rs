@@ -1,9 +1,19 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::path::{Path, PathBuf};
 
-use crate::context::Context;
+use super::Level;
+#[cfg(not(target_family = "wasm"))]
+use crate::fs;
+use crate::save::Save;
 
-#[derive(Debug, Deserialize, Clone)]
+/// dir within the assets dir and the data dir that packs are loaded from
+pub const PACKS_DIR: &str = "packs";
+/// file in the assets dir listing the bundled packs, for the web build where dirs can't be read
+pub const PACKS_MANIFEST_FILE: &str = "packs.toml";
+
+/// from easiest to hardest, which is the order packs are listed in
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Difficulty {
     Easy,
     Medium,
@@ -41,6 +51,71 @@ pub struct Pack {
     pub file: Option<String>,
     /// the general difficulty level of the pack
     pub difficulty: Difficulty,
+    /// where the pack is listed among packs of the same difficulty, lowest first
+    #[serde(default)]
+    pub order: i32,
+    /// which levels in the pack can be played
+    #[serde(default)]
+    pub progression: Progression,
+    /// what needs to be done before the pack can be played, if anything
+    #[serde(default)]
+    pub requires: Option<PackRequirement>,
+}
+
+/// rules for which levels in a pack are unlocked
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(tag = "type")]
+pub enum Progression {
+    /// every level can be played
+    #[default]
+    Open,
+    /// each level unlocks when the one before it is completed
+    Linear,
+    /// levels can be played up to this many incomplete levels into the pack, so some can be
+    /// skipped
+    Ahead { count: usize },
+}
+
+impl Progression {
+    /// whether the level at index is unlocked, given which levels in the pack are complete
+    pub fn is_level_unlocked(&self, completed: &[bool], index: usize) -> bool {
+        let allowed_incomplete = match self {
+            Progression::Open => return true,
+            Progression::Linear => 1,
+            Progression::Ahead { count } => (*count).max(1),
+        };
+        let incomplete_before = completed.iter().take(index).filter(|c| !**c).count();
+        incomplete_before < allowed_incomplete
+    }
+}
+
+/// levels of another pack that need to be completed to unlock a pack
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct PackRequirement {
+    /// slug of the other pack
+    pub pack: String,
+    /// how many of its levels need to be completed
+    pub levels: usize,
+}
+
+/// a pack file that couldn't be loaded, kept around to show the player why
+#[derive(Debug, Clone)]
+pub struct BrokenPack {
+    pub file: String,
+    pub reason: String,
+}
+
+/// the result of loading every pack that could be found
+#[derive(Default)]
+pub struct DiscoveredPacks {
+    pub packs: Vec<Pack>,
+    pub broken: Vec<BrokenPack>,
+}
+
+#[derive(Deserialize)]
+struct PacksManifest {
+    /// pack file names within the packs dir
+    packs: Vec<String>,
 }
 
 /// a level defined in a pack file
@@ -50,16 +125,509 @@ pub struct PackLevel {
     pub title: String,
     /// grid of the puzzle's elements
     pub data: String,
+    /// fewest moves needed to solve the level, for awarding medals
+    #[serde(default)]
+    pub par_moves: Option<i32>,
+    /// fewest pushes needed to solve the level, for awarding medals
+    #[serde(default)]
+    pub par_pushes: Option<i32>,
+}
+
+/// how close to par the player solved a level in, from worst to best
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl fmt::Display for Medal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Medal::Bronze => "Bronze",
+            Medal::Silver => "Silver",
+            Medal::Gold => "Gold",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// how far over par a result can be, as a ratio, and still earn silver
+const SILVER_PAR_RATIO: f32 = 1.5;
+
+impl Medal {
+    /// the medal earned for solving a level in the steps and pushes, compared against whichever
+    /// of the par values are set; levels without a par don't award medals
+    ///
+    /// gold is at or under par, silver is within 50% of it, and bronze is anything else
+    pub fn for_result(
+        steps: i32,
+        pushes: i32,
+        par_moves: Option<i32>,
+        par_pushes: Option<i32>,
+    ) -> Option<Self> {
+        let worst_ratio = [(steps, par_moves), (pushes, par_pushes)]
+            .into_iter()
+            .filter_map(|(result, par)| par.map(|par| result as f32 / par.max(1) as f32))
+            .reduce(f32::max)?;
+
+        if worst_ratio <= 1. {
+            Some(Medal::Gold)
+        } else if worst_ratio <= SILVER_PAR_RATIO {
+            Some(Medal::Silver)
+        } else {
+            Some(Medal::Bronze)
+        }
+    }
 }
 
 impl Pack {
-    pub async fn load(_ctx: &mut Context, pack_file: &str) -> Self {
+    /// loads every pack that can be found, sorted by difficulty, then order, then title
+    ///
+    /// on desktop, that's every pack in the assets packs dir and the user packs dir in the data
+    /// dir; on web, it's the packs listed in the manifest
+    ///
+    /// packs that fail to load are skipped and returned as broken instead, as is the manifest
+    /// when it can't be read
+    pub async fn load_all(base_assets_path: &Path) -> DiscoveredPacks {
+        let mut packs = vec![];
+        let mut broken = vec![];
+        let pack_files = discover_pack_files(base_assets_path)
+            .await
+            .unwrap_or_else(|manifest| {
+                broken.push(manifest);
+                vec![]
+            });
+        for pack_file in pack_files {
+            match Pack::load(&pack_file).await {
+                Ok(pack) => packs.push(pack),
+                Err(reason) => broken.push(BrokenPack {
+                    file: pack_file,
+                    reason,
+                }),
+            }
+        }
+        sort_packs(&mut packs);
+        DiscoveredPacks { packs, broken }
+    }
+
+    /// whether each level in the pack has been completed, in order
+    pub fn completed_levels(&self, save: &Save) -> Vec<bool> {
+        self.levels
+            .iter()
+            .map(|l| save.is_level_complete(&self.slug, &l.title))
+            .collect()
+    }
+
+    /// whether the level at index can be played according to the pack's progression
+    pub fn is_level_unlocked(&self, save: &Save, index: usize) -> bool {
+        self.progression
+            .is_level_unlocked(&self.completed_levels(save), index)
+    }
+
+    /// whether the pack's requirement, if any, has been met
+    pub fn is_unlocked(&self, save: &Save) -> bool {
+        self.requires
+            .as_ref()
+            .is_none_or(|r| save.completed_level_count_in_pack(&r.pack) >= r.levels)
+    }
+
+    /// what needs to be done to unlock the pack, if it's locked, naming the required pack by its
+    /// title when it's one of the packs
+    pub fn unlock_text(&self, save: &Save, packs: &[Pack]) -> Option<String> {
+        if self.is_unlocked(save) {
+            return None;
+        }
+        let requirement = self.requires.as_ref()?;
+        let required_pack_title = packs
+            .iter()
+            .find(|p| p.slug == requirement.pack)
+            .map_or(requirement.pack.as_str(), |p| p.title.as_str());
+        Some(format!(
+            "complete {} levels of {} to unlock",
+            requirement.levels, required_pack_title
+        ))
+    }
+
+    /// reads and parses the pack file, returning why it's broken if it can't be played
+    pub async fn load(pack_file: &str) -> Result<Self, String> {
         let level_pack_str = macroquad::file::load_string(pack_file)
             .await
-            .expect("Unable to read file");
-        Pack {
+            .map_err(|_| "unable to read file".to_string())?;
+        Ok(Pack {
+            file: Some(pack_file.to_string()),
+            ..Self::parse(level_pack_str.as_str())?
+        })
+    }
+
+    /// like `load_all`, but reads the packs right away instead of waiting on async loads, for
+    /// use outside of the game loop
+    #[cfg(not(target_family = "wasm"))]
+    pub fn read_all(base_assets_path: &Path) -> DiscoveredPacks {
+        let mut packs = vec![];
+        let mut broken = vec![];
+        for pack_file in local_pack_files(base_assets_path) {
+            match Pack::read(&pack_file) {
+                Ok(pack) => packs.push(pack),
+                Err(reason) => broken.push(BrokenPack {
+                    file: pack_file,
+                    reason,
+                }),
+            }
+        }
+        sort_packs(&mut packs);
+        DiscoveredPacks { packs, broken }
+    }
+
+    /// like `load`, but reads the file right away instead of waiting on an async load
+    #[cfg(not(target_family = "wasm"))]
+    pub fn read(pack_file: &str) -> Result<Self, String> {
+        let level_pack_str =
+            std::fs::read_to_string(pack_file).map_err(|_| "unable to read file".to_string())?;
+        Ok(Pack {
             file: Some(pack_file.to_string()),
-            ..toml::from_str(level_pack_str.as_str()).unwrap()
+            ..Self::parse(level_pack_str.as_str())?
+        })
+    }
+
+    /// parses the pack from TOML, checking that it has levels and that each one is valid
+    pub fn parse(toml_str: &str) -> Result<Self, String> {
+        let pack: Pack = toml::from_str(toml_str).map_err(|e| describe_toml_error(&e, toml_str))?;
+
+        if pack.levels.is_empty() {
+            return Err("pack has no levels".to_string());
+        }
+        for pack_level in &pack.levels {
+            let level = Level::parse(pack_level)
+                .map_err(|e| format!("level \"{}\": {}", pack_level.title, e))?;
+            if !level.is_valid() {
+                return Err(format!(
+                    "level \"{}\": needs one player and a goal for each crate",
+                    pack_level.title
+                ));
+            }
         }
+
+        Ok(pack)
+    }
+}
+
+/// condenses the TOML error down to one line with where it happened, since the full error
+/// includes a snippet of the file that doesn't fit on screen
+pub(crate) fn describe_toml_error(error: &toml::de::Error, toml_str: &str) -> String {
+    match error.span() {
+        Some(span) => {
+            let before = &toml_str[..span.start.min(toml_str.len())];
+            let line = before.matches('\n').count() + 1;
+            let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+            format!("{} (line {}, column {})", error.message(), line, column)
+        }
+        None => error.message().to_string(),
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+async fn discover_pack_files(base_assets_path: &Path) -> Result<Vec<String>, BrokenPack> {
+    Ok(local_pack_files(base_assets_path))
+}
+
+/// the pack files in the assets packs dir and the user packs dir
+#[cfg(not(target_family = "wasm"))]
+fn local_pack_files(base_assets_path: &Path) -> Vec<String> {
+    let mut pack_files = pack_files_in_dir(&base_assets_path.join(PACKS_DIR));
+    pack_files.extend(pack_files_in_dir(&fs::user_packs_dir()));
+    pack_files
+}
+
+/// the pack files listed in the manifest, or the manifest as a broken pack when it can't be read,
+/// since then no packs can be found
+#[cfg(target_family = "wasm")]
+async fn discover_pack_files(base_assets_path: &Path) -> Result<Vec<String>, BrokenPack> {
+    let manifest_file = base_assets_path.join(PACKS_MANIFEST_FILE);
+    let manifest_file = manifest_file.to_str().unwrap();
+    let broken = |reason: String| BrokenPack {
+        file: manifest_file.to_string(),
+        reason,
+    };
+    let manifest_str = macroquad::file::load_string(manifest_file)
+        .await
+        .map_err(|_| broken("unable to read the packs manifest".to_string()))?;
+    let pack_files = parse_manifest(&manifest_str).map_err(broken)?;
+    Ok(pack_files
+        .into_iter()
+        .map(|name| {
+            base_assets_path
+                .join(PACKS_DIR)
+                .join(name)
+                .to_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect())
+}
+
+/// returns the pack file names listed in the manifest, or why it's invalid
+pub fn parse_manifest(manifest_str: &str) -> Result<Vec<String>, String> {
+    let manifest: PacksManifest = toml::from_str(manifest_str).map_err(|e| {
+        format!(
+            "the packs manifest is invalid: {}",
+            describe_toml_error(&e, manifest_str)
+        )
+    })?;
+    Ok(manifest.packs)
+}
+
+/// paths of the TOML files in the dir, sorted by name; a missing dir has no packs
+pub fn pack_files_in_dir(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut pack_files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    pack_files.sort();
+    pack_files
+        .iter()
+        .map(|path| path.to_str().unwrap().to_string())
+        .collect()
+}
+
+fn sort_packs(packs: &mut [Pack]) {
+    packs.sort_by(|a, b| {
+        (&a.difficulty, a.order, &a.title).cmp(&(&b.difficulty, b.order, &b.title))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_medal_for_result() {
+        let medal = |steps, pushes| Medal::for_result(steps, pushes, Some(10), Some(4));
+        assert_eq!(medal(10, 4), Some(Medal::Gold));
+        assert_eq!(medal(9, 4), Some(Medal::Gold));
+        assert_eq!(medal(15, 4), Some(Medal::Silver));
+        assert_eq!(medal(10, 7), Some(Medal::Bronze));
+        assert_eq!(medal(16, 4), Some(Medal::Bronze));
+    }
+
+    fn pack(title: &str, difficulty: Difficulty, order: i32) -> Pack {
+        Pack {
+            title: title.to_string(),
+            slug: title.to_lowercase(),
+            description: String::new(),
+            author: String::new(),
+            license: String::new(),
+            year: 2024,
+            levels: vec![],
+            version: "0.1".to_string(),
+            file: None,
+            difficulty,
+            order,
+            progression: Progression::Open,
+            requires: None,
+        }
+    }
+
+    const PACK_HEADER: &str = r#"
+title = "Test Pack"
+slug = "test"
+description = ""
+author = ""
+license = ""
+difficulty = "Easy"
+year = 2024
+version = "0.1"
+"#;
+
+    #[test]
+    fn test_parse_valid_pack() {
+        let toml_str = format!(
+            "{}\n[[levels]]\ntitle = \"Level 1\"\ndata = '''\n#####\n#@$.#\n#####\n'''\n",
+            PACK_HEADER
+        );
+        let pack = Pack::parse(&toml_str).unwrap();
+        assert_eq!(pack.levels.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_missing_field() {
+        let toml_str = PACK_HEADER.replace("slug = \"test\"\n", "");
+        let err = Pack::parse(&toml_str).unwrap_err();
+        assert!(err.contains("missing field `slug`"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_syntax_error_includes_line() {
+        let toml_str = format!("{}\nlevels = [", PACK_HEADER);
+        let err = Pack::parse(&toml_str).unwrap_err();
+        assert!(err.contains("line 11"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_no_levels() {
+        let toml_str = format!("{}levels = []\n", PACK_HEADER);
+        assert_eq!(Pack::parse(&toml_str).unwrap_err(), "pack has no levels");
+    }
+
+    #[test]
+    fn test_parse_broken_levels() {
+        let toml_str = format!(
+            "{}\n[[levels]]\ntitle = \"Level 1\"\ndata = '''\n#####\n#@$x#\n#####\n'''\n",
+            PACK_HEADER
+        );
+        let err = Pack::parse(&toml_str).unwrap_err();
+        assert_eq!(
+            err,
+            "level \"Level 1\": unexpected char 'x' at row 2, column 4"
+        );
+
+        let toml_str = format!(
+            "{}\n[[levels]]\ntitle = \"Level 1\"\ndata = '''\n#####\n# $.#\n#####\n'''\n",
+            PACK_HEADER
+        );
+        let err = Pack::parse(&toml_str).unwrap_err();
+        assert!(
+            err.starts_with("level \"Level 1\": needs one player"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        assert_eq!(
+            parse_manifest("packs = [\"a.toml\", \"b.toml\"]"),
+            Ok(vec!["a.toml".to_string(), "b.toml".to_string()])
+        );
+        let err = parse_manifest("packs = [\"a.toml\"\n").unwrap_err();
+        assert!(err.starts_with("the packs manifest is invalid:"), "{}", err);
+        assert!(err.contains("line"), "{}", err);
+    }
+
+    #[test]
+    fn test_sort_packs() {
+        let mut packs = vec![
+            pack("Hard", Difficulty::Hard, 0),
+            pack("Easy B", Difficulty::Easy, 0),
+            pack("Easy A", Difficulty::Easy, 0),
+            pack("Easy First", Difficulty::Easy, -1),
+            pack("Medium", Difficulty::Medium, 0),
+        ];
+        sort_packs(&mut packs);
+        let titles: Vec<&str> = packs.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["Easy First", "Easy A", "Easy B", "Medium", "Hard"]
+        );
+    }
+
+    #[test]
+    fn test_pack_files_in_dir() {
+        let dir = std::env::temp_dir().join(format!("sokoworld-packs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("b.toml"), "").unwrap();
+        std::fs::write(dir.join("a.toml"), "").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let names: Vec<String> = pack_files_in_dir(&dir)
+            .iter()
+            .map(|f| {
+                Path::new(f)
+                    .file_name()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(names, vec!["a.toml", "b.toml"]);
+        assert!(pack_files_in_dir(&dir.join("missing")).is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_progression() {
+        let completed = [true, false, false, true, false];
+        let unlocked = |progression: Progression| -> Vec<bool> {
+            (0..completed.len())
+                .map(|i| progression.is_level_unlocked(&completed, i))
+                .collect()
+        };
+        assert_eq!(unlocked(Progression::Open), vec![true; 5]);
+        assert_eq!(
+            unlocked(Progression::Linear),
+            vec![true, true, false, false, false]
+        );
+        assert_eq!(
+            unlocked(Progression::Ahead { count: 2 }),
+            vec![true, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn test_parse_progression_and_requires() {
+        let toml_str = format!(
+            "{}progression = {{ type = \"Ahead\", count = 3 }}\n\
+             requires = {{ pack = \"pack-a\", levels = 10 }}\n\
+             [[levels]]\ntitle = \"Level 1\"\ndata = '''\n#####\n#@$.#\n#####\n'''\n",
+            PACK_HEADER
+        );
+        let pack = Pack::parse(&toml_str).unwrap();
+        assert_eq!(pack.progression, Progression::Ahead { count: 3 });
+        assert_eq!(
+            pack.requires,
+            Some(PackRequirement {
+                pack: "pack-a".to_string(),
+                levels: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_pack_requirement() {
+        let mut locked = pack("B", Difficulty::Easy, 0);
+        locked.requires = Some(PackRequirement {
+            pack: "a".to_string(),
+            levels: 2,
+        });
+        let mut save = Save::default();
+        assert!(!locked.is_unlocked(&save));
+        save.complete_level("a".to_string(), "Level 1".to_string(), 1, 1, None);
+        save.complete_level("b".to_string(), "Level 1".to_string(), 1, 1, None);
+        assert!(!locked.is_unlocked(&save));
+        save.complete_level("a".to_string(), "Level 2".to_string(), 1, 1, None);
+        assert!(locked.is_unlocked(&save));
+    }
+
+    #[test]
+    fn test_unlock_text() {
+        let mut locked = pack("B", Difficulty::Easy, 0);
+        locked.requires = Some(PackRequirement {
+            pack: "a".to_string(),
+            levels: 2,
+        });
+        let mut save = Save::default();
+        assert_eq!(
+            locked.unlock_text(&save, &[pack("A", Difficulty::Easy, 0)]),
+            Some("complete 2 levels of A to unlock".to_string())
+        );
+        assert_eq!(
+            locked.unlock_text(&save, &[]),
+            Some("complete 2 levels of a to unlock".to_string())
+        );
+        save.complete_level("a".to_string(), "Level 1".to_string(), 1, 1, None);
+        save.complete_level("a".to_string(), "Level 2".to_string(), 1, 1, None);
+        assert_eq!(locked.unlock_text(&save, &[]), None);
+    }
+
+    #[test]
+    fn test_medal_for_result_with_single_par() {
+        assert_eq!(Medal::for_result(10, 99, Some(10), None), Some(Medal::Gold));
+        assert_eq!(Medal::for_result(99, 3, None, Some(2)), Some(Medal::Silver));
+        assert_eq!(Medal::for_result(1, 1, None, None), None);
     }
 }
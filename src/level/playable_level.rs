@@ -1,6 +1,7 @@
-use super::pack::PackLevel;
+use super::pack::{Medal, PackLevel};
 use super::Level;
-use crate::audio::play_sfx;
+use crate::achievement::{self, Event};
+use crate::audio::{play_sfx, Sfx};
 use crate::color::BLUE;
 use crate::input;
 use crate::text::draw_text;
@@ -10,24 +11,41 @@ use crate::{
     vec2::Vec2,
 };
 use macroquad::color::WHITE;
-use macroquad::input::is_key_pressed;
-use macroquad::time::get_frame_time;
 
 use crate::consts::*;
 use crate::entity::{Crate, Entity};
 
-/// delay in seconds between rewind steps when held down
-const REWIND_HELD_DELAY: f32 = 0.1;
-
 /// direction that the player moved in
-#[derive(Clone, Debug)]
-enum Direction {
+#[derive(Clone, Debug, PartialEq)]
+pub enum Direction {
     Up,
     Down,
     Left,
     Right,
 }
 
+impl Direction {
+    /// how the player's position changes when moving in the direction
+    pub fn movement(&self) -> Vec2 {
+        match self {
+            Direction::Up => Vec2 { x: 0, y: -1 },
+            Direction::Down => Vec2 { x: 0, y: 1 },
+            Direction::Left => Vec2 { x: -1, y: 0 },
+            Direction::Right => Vec2 { x: 1, y: 0 },
+        }
+    }
+}
+
+/// what happened when the player tried to move or rewind
+#[derive(Debug, PartialEq)]
+pub enum MoveResult {
+    /// a wall or a crate that can't be pushed was in the way, or there was nothing to rewind
+    Blocked,
+    Moved,
+    /// pushed the crate at the index, or pulled it back when rewinding
+    Pushed(usize),
+}
+
 /// for tracking player input to make it easy to rewind
 #[derive(Clone, Debug)]
 struct PlayerMove {
@@ -45,8 +63,12 @@ pub struct PlayableLevel {
     pub level: Level,
     pub player: Entity,
     pub crates: Vec<Crate>,
-    move_held_delay: f32,
-    rewind_held_delay: f32,
+    /// whether or not any moves were rewound since the level was started or reset
+    pub rewound: bool,
+    /// medal earned for the most recent completion, if the level has a par
+    pub medal: Option<Medal>,
+    par_moves: Option<i32>,
+    par_pushes: Option<i32>,
     moves: Vec<PlayerMove>,
     pack_slug: String,
 }
@@ -77,8 +99,10 @@ impl PlayableLevel {
             level,
             crates,
             player,
-            move_held_delay: 0.,
-            rewind_held_delay: 0.,
+            rewound: false,
+            medal: None,
+            par_moves: pack_level.par_moves,
+            par_pushes: pack_level.par_pushes,
             moves: vec![],
             pack_slug,
         }
@@ -105,16 +129,19 @@ impl PlayableLevel {
         self.pushes = 0;
         self.moves.clear();
         self.complete = false;
+        self.rewound = false;
+        self.medal = None;
     }
 
     pub fn update(&mut self, ctx: &mut Context) {
-        if input::action_pressed(input::Action::Reset, &ctx.gamepads) {
+        if input::action_pressed(input::Action::Reset, ctx) {
             self.reset();
-            play_sfx(ctx, &ctx.audio.sfx.reset);
+            play_sfx(ctx, Sfx::Reset);
+            ctx.save.record_attempt(&self.pack_slug, &self.level.title);
         }
 
         // TODO: move to a game setting
-        if is_key_pressed(macroquad::miniquad::KeyCode::Key0) {
+        if input::action_pressed(input::Action::CycleTileset, ctx) {
             ctx.tileset = match ctx.tileset {
                 crate::tile::Tileset::Retro => crate::tile::Tileset::Doggo,
                 crate::tile::Tileset::Doggo => crate::tile::Tileset::Kenney,
@@ -124,57 +151,25 @@ impl PlayableLevel {
         }
 
         if self.complete {
-            if input::action_pressed(input::Action::Confirm, &ctx.gamepads) {
+            if input::action_pressed(input::Action::Confirm, ctx) {
                 ctx.load_next_level = true;
             }
             return;
         }
 
-        if self.move_held_delay > 0.0 {
-            self.move_held_delay -= get_frame_time();
-        }
-
-        if self.rewind_held_delay > 0.0 {
-            self.rewind_held_delay -= get_frame_time();
-        }
-
-        let rewind = input::action_pressed(input::Action::Rewind, &ctx.gamepads)
-            || (input::action_down(input::Action::Rewind, &ctx.gamepads)
-                && self.rewind_held_delay <= 0.);
+        let rewind = input::action_repeated(input::Action::Rewind, ctx);
 
         if rewind {
-            if !self.moves.is_empty() {
-                self.rewind_held_delay = REWIND_HELD_DELAY;
-
-                if let Some(m) = self.moves.pop() {
-                    play_sfx(ctx, &ctx.audio.sfx.footstep);
-                    self.steps -= 1;
-                    if m.crate_moved_index.is_some() {
-                        self.pushes -= 1;
-                    }
-                    let reverse_move = match m.direction {
-                        Direction::Up => {
-                            // move down
-                            Vec2 { x: 0, y: 1 }
+            match self.rewind() {
+                MoveResult::Blocked => play_sfx(ctx, Sfx::CantMove),
+                result => {
+                    play_sfx(ctx, Sfx::Footstep);
+                    if let MoveResult::Pushed(crate_index) = result {
+                        if self.crates[crate_index].on_storage_location {
+                            play_sfx(ctx, Sfx::CrateOnStorageLocation);
                         }
-                        Direction::Down => {
-                            // move up
-                            Vec2 { x: 0, y: -1 }
-                        }
-                        Direction::Left => {
-                            // move right
-                            Vec2 { x: 1, y: 0 }
-                        }
-                        Direction::Right => {
-                            // move left
-                            Vec2 { x: -1, y: 0 }
-                        }
-                    };
-                    self.player.pos.add(reverse_move);
-                    self.reverse_move_crate(ctx, m.crate_moved_index, reverse_move);
+                    }
                 }
-            } else {
-                play_sfx(ctx, &ctx.audio.sfx.cant_move);
             }
         } else {
             self.handle_movement(ctx)
@@ -184,100 +179,170 @@ impl PlayableLevel {
     fn handle_movement(&mut self, ctx: &mut Context) {
         let mut move_player = Vec2 { x: 0, y: 0 };
 
-        if input::action_pressed(input::Action::Up, &ctx.gamepads)
-            || (input::action_down(input::Action::Up, &ctx.gamepads) && self.move_held_delay <= 0.)
-        {
+        if input::action_repeated(input::Action::Up, ctx) {
             move_player.y = -1;
-        } else if input::action_pressed(input::Action::Down, &ctx.gamepads)
-            || (input::action_down(input::Action::Down, &ctx.gamepads)
-                && self.move_held_delay <= 0.)
-        {
+        } else if input::action_repeated(input::Action::Down, ctx) {
             move_player.y = 1;
-        } else if input::action_pressed(input::Action::Left, &ctx.gamepads)
-            || (input::action_down(input::Action::Left, &ctx.gamepads)
-                && self.move_held_delay <= 0.)
-        {
+        } else if input::action_repeated(input::Action::Left, ctx) {
             move_player.x = -1;
-        } else if input::action_pressed(input::Action::Right, &ctx.gamepads)
-            || (input::action_down(input::Action::Right, &ctx.gamepads)
-                && self.move_held_delay <= 0.)
-        {
+        } else if input::action_repeated(input::Action::Right, ctx) {
             move_player.x = 1;
         }
 
-        let new_player_pos = self.player.pos.clone().add(move_player).to_owned();
-        let crate_at_new_player_pos = self.crates.iter().find(|c| c.pos == new_player_pos);
-        let mut move_crate = false;
-
         if !move_player.is_zero() {
-            self.move_held_delay = MOVE_HELD_DELAY;
-
-            match crate_at_new_player_pos {
-                Some(c) => {
-                    let new_crate_pos = c.pos.clone().add(move_player).to_owned();
-                    let wall_at_new_crate_pos =
-                        self.level.walls.iter().find(|w| *w == &new_crate_pos);
-                    let other_crate_at_new_crate_pos =
-                        self.crates.iter().find(|c| c.pos == new_crate_pos);
-
-                    if wall_at_new_crate_pos.is_none() && other_crate_at_new_crate_pos.is_none() {
-                        let crate_i = self.crates.iter().position(|ic| ic.pos == c.pos);
-                        self.move_player_to(ctx, &move_player, &new_player_pos, crate_i);
-                        move_crate = true;
-                    } else {
-                        play_sfx(ctx, &ctx.audio.sfx.cant_move);
-                    }
-                }
-                None => {
-                    let wall_at_new_player_pos =
-                        self.level.walls.iter().find(|w| *w == &new_player_pos);
-                    match wall_at_new_player_pos {
-                        None => {
-                            self.move_player_to(ctx, &move_player, &new_player_pos, None);
-                        }
-                        Some(_) => {
-                            play_sfx(ctx, &ctx.audio.sfx.cant_move);
+            match self.make_move(&move_player) {
+                MoveResult::Blocked => play_sfx(ctx, Sfx::CantMove),
+                result => {
+                    play_sfx(ctx, Sfx::Footstep);
+                    ctx.save.record_step(result != MoveResult::Moved);
+                    achievement::handle_event(ctx, Event::Step);
+
+                    if let MoveResult::Pushed(crate_index) = result {
+                        play_sfx(ctx, Sfx::Push);
+                        if self.crates[crate_index].on_storage_location {
+                            play_sfx(ctx, Sfx::CrateOnStorageLocation);
                         }
-                    };
+                    }
                 }
-            };
+            }
 
-            // this feels bad and duplicative to get around borrow checker
-            if move_crate {
-                let c = self
-                    .crates
-                    .iter_mut()
-                    .find(|c| c.pos == new_player_pos)
-                    .unwrap();
-                let new_crate_pos = c.pos.clone().add(move_player).to_owned();
+            if self.complete {
+                play_sfx(ctx, Sfx::LevelComplete);
+                self.save_completion(ctx);
+            }
+        }
+    }
+
+    /// records the completion, with its steps, pushes and medal, in the save
+    pub fn save_completion(&self, ctx: &mut Context) {
+        ctx.save.complete_level(
+            self.pack_slug.clone(),
+            self.level.title.clone(),
+            self.steps,
+            self.pushes,
+            self.medal,
+        );
+    }
+
+    /// moves the player like `apply_move`, completing the level and working out the medal when
+    /// every crate is stored; doesn't play sounds or record anything
+    pub fn make_move(&mut self, movement: &Vec2) -> MoveResult {
+        let result = self.apply_move(movement);
+        if result != MoveResult::Blocked && self.all_crates_stored() {
+            self.complete = true;
+            self.medal =
+                Medal::for_result(self.steps, self.pushes, self.par_moves, self.par_pushes);
+        }
+        result
+    }
+
+    /// undoes the last move, pulling back the crate it pushed if any; doesn't play sounds
+    pub fn rewind(&mut self) -> MoveResult {
+        let Some(m) = self.moves.pop() else {
+            return MoveResult::Blocked;
+        };
+        self.rewound = true;
+        self.steps -= 1;
+        let movement = m.direction.movement();
+        let reverse_move = Vec2 {
+            x: -movement.x,
+            y: -movement.y,
+        };
+        self.player.pos.add(reverse_move);
+
+        match m.crate_moved_index {
+            Some(i) => {
+                self.pushes -= 1;
+                let c = &mut self.crates[i];
+                c.pos.add(reverse_move);
+                c.on_storage_location = self.level.storage_locations.contains(&c.pos);
+                MoveResult::Pushed(i)
+            }
+            None => MoveResult::Moved,
+        }
+    }
+
+    /// moves the player, and the crate in front of them if there is one, updating the steps,
+    /// pushes, and move history; doesn't play sounds or record anything
+    fn apply_move(&mut self, movement: &Vec2) -> MoveResult {
+        let new_player_pos = self.player.pos.clone().add(*movement).to_owned();
+        let crate_index = self.crates.iter().position(|c| c.pos == new_player_pos);
+
+        match crate_index {
+            Some(i) => {
+                let new_crate_pos = new_player_pos.clone().add(*movement).to_owned();
+                if self.level.walls.contains(&new_crate_pos)
+                    || self.crates.iter().any(|c| c.pos == new_crate_pos)
+                {
+                    return MoveResult::Blocked;
+                }
+                let c = &mut self.crates[i];
                 c.pos = new_crate_pos;
+                c.on_storage_location = self.level.storage_locations.contains(&new_crate_pos);
                 self.pushes += 1;
-                play_sfx(ctx, &ctx.audio.sfx.push);
-
-                Self::check_for_crate_on_storage_location(
-                    ctx,
-                    c,
-                    self.level.storage_locations.clone(),
-                );
             }
+            None => {
+                if self.level.walls.contains(&new_player_pos) {
+                    return MoveResult::Blocked;
+                }
+            }
+        }
 
-            if self.crates.iter().all(|c| {
-                self.level
-                    .storage_locations
-                    .clone() // idk if cloning is right here
-                    .into_iter()
-                    .any(|sl| sl == c.pos)
-            }) {
-                play_sfx(ctx, &ctx.audio.sfx.level_complete);
-                self.complete = true;
-                ctx.save.complete_level(
-                    self.pack_slug.clone(),
-                    self.level.title.clone(),
-                    self.steps,
-                    self.pushes,
-                );
+        self.moves.push(PlayerMove {
+            direction: Self::direction_of_movement(movement),
+            crate_moved_index: crate_index,
+        });
+        self.player.pos = new_player_pos;
+        self.steps += 1;
+
+        match crate_index {
+            Some(i) => MoveResult::Pushed(i),
+            None => MoveResult::Moved,
+        }
+    }
+
+    fn all_crates_stored(&self) -> bool {
+        self.crates
+            .iter()
+            .all(|c| self.level.storage_locations.contains(&c.pos))
+    }
+
+    /// directions of every move made since the level was started or reset
+    pub fn move_history(&self) -> Vec<Direction> {
+        self.moves.iter().map(|m| m.direction.clone()).collect()
+    }
+
+    /// resets the level and then makes the moves like `make_move`, without playing sounds or
+    /// recording anything, like after the level is reloaded from disk; whether moves were
+    /// rewound is kept, since the moves being replayed may have come after a rewind
+    ///
+    /// returns false and leaves the level reset if any of the moves can't be made
+    pub fn replay(&mut self, directions: &[Direction]) -> bool {
+        let rewound = self.rewound;
+        self.reset();
+        self.rewound = rewound;
+        for direction in directions {
+            if self.make_move(&direction.movement()) == MoveResult::Blocked {
+                self.reset();
+                self.rewound = rewound;
+                return false;
             }
         }
+        true
+    }
+
+    /// steps and pushes so far, along with the par when the level has one
+    pub fn progress_text(&self) -> String {
+        let mut text = format!("Steps: {} | Pushes: {}", self.steps, self.pushes);
+        if self.par_moves.is_some() || self.par_pushes.is_some() {
+            let par = |p: Option<i32>| p.map_or("-".to_string(), |p| p.to_string());
+            text.push_str(&format!(
+                " | Par: {} / {}",
+                par(self.par_moves),
+                par(self.par_pushes)
+            ));
+        }
+        text
     }
 
     pub fn draw(&mut self, ctx: &mut Context) {
@@ -305,16 +370,40 @@ impl PlayableLevel {
         );
         draw_text(
             ctx,
-            format!("Steps: {} | Pushes: {}", self.steps, self.pushes).as_str(),
+            self.progress_text().as_str(),
             X_INSET / 2.,
             112.,
             crate::text::Size::Medium,
             WHITE,
         );
+        let controls = ctx.settings.controls();
+        let hint = |action| controls.hint(input::InputLayer::Gameplay, action);
+        let next_level = format!(
+            "Press {} to go to next level.",
+            hint(input::Action::Confirm)
+        );
+        let controls_hint = format!(
+            "Move = {} | Rewind = {} | Reset = {}",
+            controls.directions_hint(
+                input::InputLayer::Gameplay,
+                &[
+                    input::Action::Up,
+                    input::Action::Left,
+                    input::Action::Down,
+                    input::Action::Right,
+                ]
+            ),
+            hint(input::Action::Rewind),
+            hint(input::Action::Reset)
+        );
         if self.complete {
+            let message = match self.medal {
+                Some(medal) => format!("Nice job, {} medal! {}", medal, next_level),
+                None => format!("Nice job! {}", next_level),
+            };
             draw_text(
                 ctx,
-                "Nice job! Press Z to go to next level.",
+                message.as_str(),
                 X_INSET / 2.,
                 VIRTUAL_HEIGHT - 92.,
                 crate::text::Size::Medium,
@@ -323,7 +412,7 @@ impl PlayableLevel {
         }
         draw_text(
             ctx,
-            "Move = Arrow Keys | Rewind = X | Reset = C",
+            controls_hint.as_str(),
             X_INSET / 2.,
             VIRTUAL_HEIGHT - 48.,
             crate::text::Size::Small,
@@ -331,53 +420,6 @@ impl PlayableLevel {
         );
     }
 
-    fn reverse_move_crate(
-        &mut self,
-        ctx: &Context,
-        crate_moved_index: Option<usize>,
-        reverse_move: Vec2,
-    ) {
-        if let Some(idx) = crate_moved_index {
-            if let Some(c) = self.crates.get_mut(idx) {
-                c.pos.add(reverse_move);
-                Self::check_for_crate_on_storage_location(
-                    ctx,
-                    c,
-                    self.level.storage_locations.clone(),
-                )
-            }
-        }
-    }
-
-    fn check_for_crate_on_storage_location(
-        ctx: &Context,
-        c: &mut Crate,
-        storage_locations: Vec<Vec2>,
-    ) {
-        if storage_locations.into_iter().any(|sl| sl == c.pos) {
-            c.on_storage_location = true;
-            play_sfx(ctx, &ctx.audio.sfx.crate_on_storage_location);
-        } else {
-            c.on_storage_location = false;
-        }
-    }
-
-    fn move_player_to(
-        &mut self,
-        ctx: &Context,
-        movement: &Vec2,
-        new_pos: &Vec2,
-        crate_index: Option<usize>,
-    ) {
-        play_sfx(ctx, &ctx.audio.sfx.footstep);
-        self.moves.push(PlayerMove {
-            direction: Self::direction_of_movement(movement),
-            crate_moved_index: crate_index,
-        });
-        self.player.pos = *new_pos;
-        self.steps += 1;
-    }
-
     fn direction_of_movement(movement_vec: &Vec2) -> Direction {
         if movement_vec.x > 0 {
             Direction::Right
@@ -390,3 +432,90 @@ impl PlayableLevel {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playable_level(data: &str) -> PlayableLevel {
+        PlayableLevel::new(
+            "test".to_string(),
+            &PackLevel {
+                title: "test level".to_string(),
+                data: data.to_string(),
+                par_moves: None,
+                par_pushes: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_apply_move() {
+        let mut level = playable_level("######\n#@$ .#\n######");
+        assert_eq!(level.apply_move(&Vec2 { x: -1, y: 0 }), MoveResult::Blocked);
+        assert_eq!(
+            level.apply_move(&Vec2 { x: 1, y: 0 }),
+            MoveResult::Pushed(0)
+        );
+        assert_eq!(
+            level.apply_move(&Vec2 { x: 1, y: 0 }),
+            MoveResult::Pushed(0)
+        );
+        assert!(level.crates[0].on_storage_location);
+        assert_eq!((level.steps, level.pushes), (2, 2));
+        assert!(level.all_crates_stored());
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut level = playable_level("#######\n#@ $ .#\n#######");
+        level.apply_move(&Vec2 { x: 1, y: 0 });
+        level.apply_move(&Vec2 { x: 1, y: 0 });
+        let moves = level.move_history();
+        assert_eq!(moves, vec![Direction::Right, Direction::Right]);
+
+        let mut reloaded = playable_level("#######\n#@ $ .#\n#######");
+        reloaded.rewound = true;
+        assert!(reloaded.replay(&moves));
+        assert_eq!((reloaded.steps, reloaded.pushes), (2, 1));
+        assert!(!reloaded.complete);
+        assert!(reloaded.rewound);
+
+        // the crate was moved next to the storage location, so replaying completes the level
+        let mut reloaded = PlayableLevel::new(
+            "test".to_string(),
+            &PackLevel {
+                title: "test level".to_string(),
+                data: "#######\n#@ $.##\n#######".to_string(),
+                par_moves: Some(2),
+                par_pushes: Some(1),
+            },
+        );
+        assert!(reloaded.replay(&moves));
+        assert!(reloaded.complete);
+        assert_eq!(reloaded.medal, Some(Medal::Gold));
+
+        // a wall was added where the player walked
+        let mut reloaded = playable_level("#######\n#@#$ .#\n#######");
+        assert!(!reloaded.replay(&moves));
+        assert_eq!(reloaded.steps, 0);
+    }
+
+    #[test]
+    fn test_make_move_and_rewind() {
+        let mut level = playable_level("######\n#@$ .#\n######");
+        assert_eq!(level.rewind(), MoveResult::Blocked);
+        level.make_move(&Direction::Right.movement());
+        assert_eq!(
+            level.make_move(&Direction::Right.movement()),
+            MoveResult::Pushed(0)
+        );
+        assert!(level.complete);
+
+        assert_eq!(level.rewind(), MoveResult::Pushed(0));
+        assert!(!level.crates[0].on_storage_location);
+        assert_eq!(level.player.pos, Vec2 { x: 2, y: 1 });
+        assert_eq!((level.steps, level.pushes), (1, 1));
+        assert!(level.rewound);
+    }
+}
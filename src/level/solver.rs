@@ -0,0 +1,331 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use super::Level;
+
+/// how many positions the solver looks at before giving up by default, enough for the bundled
+/// packs while keeping a check of a big pack to a few seconds
+pub const DEFAULT_MAX_STATES: usize = 200_000;
+/// how much more the quick search weighs getting crates closer to goals than the pushes taken,
+/// which finds a solution much sooner but not always the one with the fewest pushes
+const QUICK_SEARCH_WEIGHT: usize = 3;
+
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+/// what the solver found out about a level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Solvability {
+    /// the level can be completed, with the fewest pushes it takes if that could be worked out
+    Solvable { fewest_pushes: Option<usize> },
+    /// every possible position was tried and none completed the level
+    Unsolvable,
+    /// the solver gave up after looking at too many positions
+    Unknown,
+}
+
+/// the level's static layout, flattened for quick lookups
+struct Grid {
+    width: i32,
+    height: i32,
+    walls: Vec<bool>,
+    goals: Vec<bool>,
+    /// fewest pushes to get a crate from each square to a goal if nothing else was in the way;
+    /// squares without one, like corners, are dead and a crate on them can't be solved
+    goal_distances: Vec<Option<usize>>,
+}
+
+impl Grid {
+    fn new(level: &Level) -> Self {
+        let width = level.width as i32;
+        let height = level.height as i32;
+        let size = (width * height) as usize;
+        let mut walls = vec![false; size];
+        let mut goals = vec![false; size];
+        for wall in &level.walls {
+            walls[(wall.y * width + wall.x) as usize] = true;
+        }
+        for goal in &level.storage_locations {
+            goals[(goal.y * width + goal.x) as usize] = true;
+        }
+
+        let mut grid = Self {
+            width,
+            height,
+            walls,
+            goals,
+            goal_distances: vec![],
+        };
+        grid.goal_distances = grid.find_goal_distances();
+        grid
+    }
+
+    /// the index of the square next to the one at index in the direction, if it's in bounds
+    fn step(&self, index: usize, (dx, dy): (i32, i32)) -> Option<usize> {
+        let x = index as i32 % self.width + dx;
+        let y = index as i32 / self.width + dy;
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            None
+        } else {
+            Some((y * self.width + x) as usize)
+        }
+    }
+
+    fn is_open(&self, index: usize) -> bool {
+        !self.walls[index]
+    }
+
+    fn is_dead(&self, index: usize) -> bool {
+        self.goal_distances[index].is_none()
+    }
+
+    /// found by pulling crates back from every goal
+    fn find_goal_distances(&self) -> Vec<Option<usize>> {
+        let mut distances: Vec<Option<usize>> =
+            self.goals.iter().map(|goal| goal.then_some(0)).collect();
+        let mut queue: VecDeque<usize> = (0..distances.len()).filter(|i| self.goals[*i]).collect();
+        while let Some(index) = queue.pop_front() {
+            let distance = distances[index].unwrap();
+            for direction in DIRECTIONS {
+                let Some(crate_from) = self.step(index, direction) else {
+                    continue;
+                };
+                let Some(player_from) = self.step(crate_from, direction) else {
+                    continue;
+                };
+                if self.is_open(crate_from)
+                    && self.is_open(player_from)
+                    && distances[crate_from].is_none()
+                {
+                    distances[crate_from] = Some(distance + 1);
+                    queue.push_back(crate_from);
+                }
+            }
+        }
+        distances
+    }
+
+    /// every square the player can walk to without pushing a crate
+    fn reachable(&self, player: usize, occupied: &[bool]) -> Vec<bool> {
+        let mut reached = vec![false; self.walls.len()];
+        reached[player] = true;
+        let mut queue = VecDeque::from([player]);
+        while let Some(index) = queue.pop_front() {
+            for direction in DIRECTIONS {
+                if let Some(next) = self.step(index, direction) {
+                    if !reached[next] && self.is_open(next) && !occupied[next] {
+                        reached[next] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        reached
+    }
+
+    /// whether the crate just pushed to index is part of a 2x2 block of crates and walls, which
+    /// can never be moved again, with a crate that isn't on a goal
+    fn is_frozen(&self, index: usize, occupied: &[bool]) -> bool {
+        let blocked = |i: Option<usize>| i.is_none_or(|i| self.walls[i] || occupied[i]);
+        let stuck_off_goal = |i: Option<usize>| i.is_some_and(|i| occupied[i] && !self.goals[i]);
+
+        [(-1, -1), (-1, 1), (1, -1), (1, 1)]
+            .into_iter()
+            .any(|(dx, dy)| {
+                let block = [
+                    Some(index),
+                    self.step(index, (dx, 0)),
+                    self.step(index, (0, dy)),
+                    self.step(index, (dx, dy)),
+                ];
+                block.iter().all(|i| blocked(*i)) && block.iter().any(|i| stuck_off_goal(*i))
+            })
+    }
+}
+
+/// how a single search over the level's positions ended
+enum SearchResult {
+    Solved {
+        pushes: usize,
+    },
+    /// every position was looked at
+    Exhausted,
+    /// max_states was reached first
+    GaveUp,
+}
+
+/// works out whether the level can be completed, and in how few pushes, looking at up to
+/// max_states positions for each
+///
+/// a quick search checks that there's a solution at all, then a slower one looks for the
+/// fewest pushes
+pub fn solve(level: &Level, max_states: usize) -> Solvability {
+    match search(level, max_states, QUICK_SEARCH_WEIGHT) {
+        SearchResult::Solved { .. } => Solvability::Solvable {
+            fewest_pushes: match search(level, max_states, 1) {
+                SearchResult::Solved { pushes } => Some(pushes),
+                _ => None,
+            },
+        },
+        SearchResult::Exhausted => Solvability::Unsolvable,
+        SearchResult::GaveUp => Solvability::Unknown,
+    }
+}
+
+/// A* search over crate positions, where the estimate of pushes left is multiplied by weight;
+/// with a weight of 1 the first solution found has the fewest pushes
+///
+/// positions that can't be completed, like a crate in a corner, are skipped
+fn search(level: &Level, max_states: usize, weight: usize) -> SearchResult {
+    let grid = Grid::new(level);
+    let to_index = |x: i32, y: i32| (y * grid.width + x) as usize;
+    let estimate = |crates: &[usize]| -> usize {
+        crates
+            .iter()
+            .map(|c| grid.goal_distances[*c].unwrap_or(0))
+            .sum()
+    };
+    let occupancy = |crates: &[usize]| -> Vec<bool> {
+        let mut occupied = vec![false; grid.walls.len()];
+        for c in crates {
+            occupied[*c] = true;
+        }
+        occupied
+    };
+    // the player's position is stored as the lowest index they can reach, since any spot in the
+    // same area can get to the same pushes
+    let normalize = |player: usize, occupied: &[bool]| -> usize {
+        grid.reachable(player, occupied)
+            .iter()
+            .position(|r| *r)
+            .unwrap_or(player)
+    };
+
+    let mut crates: Vec<usize> = level.crates.iter().map(|c| to_index(c.x, c.y)).collect();
+    crates.sort();
+    if crates.iter().any(|c| grid.is_dead(*c)) {
+        return SearchResult::Exhausted;
+    }
+    let player = to_index(level.player.x, level.player.y);
+
+    // fewest pushes found so far to get to each position
+    let mut best: HashMap<(Vec<usize>, usize), usize> = HashMap::new();
+    best.insert((crates.clone(), normalize(player, &occupancy(&crates))), 0);
+    // ties are broken by the most pushes so far, since those are closest to being solved
+    let mut queue = BinaryHeap::from([Reverse((
+        weight * estimate(&crates),
+        Reverse(0),
+        crates,
+        player,
+    ))]);
+
+    while let Some(Reverse((_, Reverse(pushes), crates, player))) = queue.pop() {
+        if crates.iter().all(|c| grid.goals[*c]) {
+            return SearchResult::Solved { pushes };
+        }
+
+        let mut occupied = occupancy(&crates);
+        let reachable = grid.reachable(player, &occupied);
+        for (crate_index, crate_pos) in crates.iter().enumerate() {
+            for direction in DIRECTIONS {
+                let (dx, dy) = direction;
+                let Some(push_from) = grid.step(*crate_pos, (-dx, -dy)) else {
+                    continue;
+                };
+                let Some(push_to) = grid.step(*crate_pos, direction) else {
+                    continue;
+                };
+                if !reachable[push_from]
+                    || !grid.is_open(push_to)
+                    || grid.is_dead(push_to)
+                    || occupied[push_to]
+                {
+                    continue;
+                }
+
+                occupied[*crate_pos] = false;
+                occupied[push_to] = true;
+                let frozen = grid.is_frozen(push_to, &occupied);
+                let normalized_player = normalize(*crate_pos, &occupied);
+                occupied[push_to] = false;
+                occupied[*crate_pos] = true;
+                if frozen {
+                    continue;
+                }
+
+                let mut next_crates = crates.clone();
+                next_crates[crate_index] = push_to;
+                next_crates.sort();
+                let key = (next_crates, normalized_player);
+                if best.get(&key).is_some_and(|b| *b <= pushes + 1) {
+                    continue;
+                }
+                if best.len() >= max_states {
+                    return SearchResult::GaveUp;
+                }
+                let next_crates = key.0.clone();
+                best.insert(key, pushes + 1);
+                queue.push(Reverse((
+                    pushes + 1 + weight * estimate(&next_crates),
+                    Reverse(pushes + 1),
+                    next_crates,
+                    *crate_pos,
+                )));
+            }
+        }
+    }
+
+    SearchResult::Exhausted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::pack::PackLevel;
+
+    fn parse(data: &str) -> Level {
+        Level::parse(&PackLevel {
+            title: "test level".to_string(),
+            data: data.to_string(),
+            par_moves: None,
+            par_pushes: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_solvable() {
+        let level = parse("#####\n#@$.#\n#####");
+        assert_eq!(
+            solve(&level, DEFAULT_MAX_STATES),
+            Solvability::Solvable {
+                fewest_pushes: Some(1)
+            }
+        );
+
+        // the crate has to go around the wall
+        let level = parse("######\n#    #\n#    #\n#@$#.#\n#    #\n######");
+        assert_eq!(
+            solve(&level, DEFAULT_MAX_STATES),
+            Solvability::Solvable {
+                fewest_pushes: Some(4)
+            }
+        );
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        // crate stuck in a corner
+        let level = parse("#####\n#$ .#\n# @ #\n#####");
+        assert_eq!(solve(&level, DEFAULT_MAX_STATES), Solvability::Unsolvable);
+
+        // crate can only be pushed away from the goal
+        let level = parse("######\n#.@$ #\n######");
+        assert_eq!(solve(&level, DEFAULT_MAX_STATES), Solvability::Unsolvable);
+    }
+
+    #[test]
+    fn test_gives_up() {
+        let level = parse("#######\n#@    #\n# $$  #\n#  .. #\n#######");
+        assert_eq!(solve(&level, 1), Solvability::Unknown);
+    }
+}
@@ -1,5 +1,7 @@
+pub mod achievement;
 pub mod assets_path;
 pub mod audio;
+pub mod cli;
 pub mod color;
 pub mod consts;
 pub mod context;
@@ -8,10 +10,15 @@ pub mod font;
 pub mod fs;
 pub mod input;
 pub mod level;
+pub mod lint;
+pub mod profile;
 pub mod save;
 pub mod scene;
 pub mod settings;
+pub mod storage;
 pub mod text;
 pub mod texture;
 pub mod tile;
+pub mod toast;
+pub mod transition;
 pub mod vec2;
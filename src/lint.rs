@@ -0,0 +1,342 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::level::pack::{describe_toml_error, Pack};
+use crate::level::solver::{solve, Solvability};
+use crate::level::Level;
+
+/// how serious a problem with a pack is; errors make the pack unplayable or break saves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// a problem found in a pack file
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: String,
+    /// title of the level the problem is in, if it's not with the pack as a whole
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.level {
+            Some(level) => write!(
+                f,
+                "{}: {}: level \"{}\": {}",
+                self.file, self.severity, level, self.message
+            ),
+            None => write!(f, "{}: {}: {}", self.file, self.severity, self.message),
+        }
+    }
+}
+
+/// collects diagnostics for one file at a time
+struct Linter {
+    diagnostics: Vec<Diagnostic>,
+    file: String,
+}
+
+impl Linter {
+    fn report(&mut self, severity: Severity, level: Option<&str>, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            file: self.file.clone(),
+            level: level.map(|l| l.to_string()),
+            message,
+        });
+    }
+}
+
+/// checks the packs, given as file name and contents, returning every problem found
+///
+/// each level is checked for solvability, looking at up to max_states positions
+pub fn lint_packs(files: &[(String, String)], max_states: usize) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        diagnostics: vec![],
+        file: String::new(),
+    };
+    // what's been seen so far across every pack, to find duplicates, mapped to where it was seen
+    let mut slugs: HashMap<String, String> = HashMap::new();
+    let mut titles: HashMap<String, String> = HashMap::new();
+    let mut layouts: HashMap<String, String> = HashMap::new();
+    // checked once every slug is known, file name mapped to the required pack's slug
+    let mut requirements: Vec<(String, String)> = vec![];
+
+    for (file, toml_str) in files {
+        linter.file = file.clone();
+        let pack: Pack = match toml::from_str(toml_str) {
+            Ok(pack) => pack,
+            Err(e) => {
+                linter.report(Severity::Error, None, describe_toml_error(&e, toml_str));
+                continue;
+            }
+        };
+
+        if let Some(other) = slugs.insert(pack.slug.clone(), file.clone()) {
+            linter.report(
+                Severity::Error,
+                None,
+                format!("slug \"{}\" is also used by {}", pack.slug, other),
+            );
+        }
+        if let Some(other) = titles.insert(pack.title.clone(), file.clone()) {
+            linter.report(
+                Severity::Warning,
+                None,
+                format!("title \"{}\" is also used by {}", pack.title, other),
+            );
+        }
+        for (field, value) in [("author", &pack.author), ("license", &pack.license)] {
+            if value.trim().is_empty() {
+                linter.report(Severity::Error, None, format!("{} is empty", field));
+            }
+        }
+        if pack.levels.is_empty() {
+            linter.report(Severity::Error, None, "pack has no levels".to_string());
+        }
+        if let Some(requirement) = &pack.requires {
+            requirements.push((file.clone(), requirement.pack.clone()));
+        }
+
+        let mut level_titles: Vec<&str> = vec![];
+        for pack_level in &pack.levels {
+            let title = pack_level.title.as_str();
+            if level_titles.contains(&title) {
+                linter.report(
+                    Severity::Error,
+                    Some(title),
+                    "title is used by another level in the pack".to_string(),
+                );
+            }
+            level_titles.push(title);
+
+            let location = format!("level \"{}\" in {}", title, file);
+            if let Some(other) = layouts.insert(normalize_layout(&pack_level.data), location) {
+                linter.report(
+                    Severity::Warning,
+                    Some(title),
+                    format!("same layout as {}", other),
+                );
+            }
+
+            let level = match Level::parse(pack_level) {
+                Ok(level) => level,
+                Err(e) => {
+                    linter.report(Severity::Error, Some(title), e);
+                    continue;
+                }
+            };
+            if !level.is_valid() {
+                linter.report(
+                    Severity::Error,
+                    Some(title),
+                    "needs one player and a goal for each crate".to_string(),
+                );
+                continue;
+            }
+
+            match solve(&level, max_states) {
+                Solvability::Solvable {
+                    fewest_pushes: Some(pushes),
+                } => {
+                    for (field, par) in [
+                        ("par_moves", pack_level.par_moves),
+                        ("par_pushes", pack_level.par_pushes),
+                    ] {
+                        if par.is_some_and(|par| (par as usize) < pushes) {
+                            linter.report(
+                                Severity::Error,
+                                Some(title),
+                                format!(
+                                    "{} is lower than the fewest pushes possible ({})",
+                                    field, pushes
+                                ),
+                            );
+                        }
+                    }
+                }
+                Solvability::Solvable {
+                    fewest_pushes: None,
+                } => {}
+                Solvability::Unsolvable => linter.report(
+                    Severity::Error,
+                    Some(title),
+                    "level can't be solved".to_string(),
+                ),
+                Solvability::Unknown => linter.report(
+                    Severity::Warning,
+                    Some(title),
+                    format!(
+                        "couldn't tell if the level can be solved within {} positions",
+                        max_states
+                    ),
+                ),
+            }
+        }
+    }
+
+    for (file, required_slug) in requirements {
+        if !slugs.contains_key(&required_slug) {
+            linter.file = file;
+            linter.report(
+                Severity::Warning,
+                None,
+                format!(
+                    "requires pack \"{}\", which isn't one of the packs checked",
+                    required_slug
+                ),
+            );
+        }
+    }
+
+    linter.diagnostics
+}
+
+/// the level data without differences that don't matter, like which floor char is used or
+/// trailing spaces, for finding duplicate levels
+fn normalize_layout(data: &str) -> String {
+    data.lines()
+        .map(|line| line.replace(['-', '_'], " ").trim_end().to_string())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::solver::DEFAULT_MAX_STATES;
+
+    fn pack(slug: &str, levels: &[(&str, &str)]) -> String {
+        let mut toml_str = format!(
+            "title = \"{}\"\nslug = \"{}\"\ndescription = \"\"\nauthor = \"Someone\"\n\
+             license = \"CC0\"\ndifficulty = \"Easy\"\nyear = 2024\nversion = \"0.1\"\n",
+            slug, slug
+        );
+        for (title, data) in levels {
+            toml_str.push_str(&format!(
+                "\n[[levels]]\ntitle = \"{}\"\ndata = '''\n{}\n'''\n",
+                title, data
+            ));
+        }
+        toml_str
+    }
+
+    fn messages(files: &[(&str, String)]) -> Vec<String> {
+        let files: Vec<(String, String)> = files
+            .iter()
+            .map(|(name, contents)| (name.to_string(), contents.clone()))
+            .collect();
+        lint_packs(&files, DEFAULT_MAX_STATES)
+            .iter()
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_pack() {
+        let pack = pack("a", &[("Level 1", "#####\n#@$.#\n#####")]);
+        assert!(messages(&[("a.toml", pack)]).is_empty());
+    }
+
+    #[test]
+    fn test_schema_and_license() {
+        let broken = "title = \"A\"".to_string();
+        let unlicensed = pack("b", &[("Level 1", "#####\n#@$.#\n#####")])
+            .replace("license = \"CC0\"", "license = \"\"");
+        assert_eq!(
+            messages(&[("a.toml", broken), ("b.toml", unlicensed)]),
+            vec![
+                "a.toml: error: missing field `slug` (line 1, column 1)",
+                "b.toml: error: license is empty",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let a = pack(
+            "a",
+            &[
+                ("Level 1", "#####\n#@$.#\n#####"),
+                ("Level 1", "######\n#@$ .#\n######"),
+            ],
+        );
+        let b = pack("a", &[("Level 1", "#####\n#@$.#\n#####  ")]);
+        assert_eq!(
+            messages(&[("a.toml", a), ("b.toml", b)]),
+            vec![
+                "a.toml: error: level \"Level 1\": title is used by another level in the pack",
+                "b.toml: error: slug \"a\" is also used by a.toml",
+                "b.toml: warning: title \"a\" is also used by a.toml",
+                "b.toml: warning: level \"Level 1\": same layout as level \"Level 1\" in a.toml",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_required_pack() {
+        let a = pack("a", &[("Level 1", "#####\n#@$.#\n#####")]);
+        let b = pack("b", &[("Level 1", "######\n#@$ .#\n######")]).replace(
+            "[[levels]]",
+            "requires = { pack = \"a\", levels = 1 }\n[[levels]]",
+        );
+        let c = pack("c", &[("Level 1", "#######\n#@$  .#\n#######")]).replace(
+            "[[levels]]",
+            "requires = { pack = \"z\", levels = 1 }\n[[levels]]",
+        );
+        assert_eq!(
+            messages(&[("a.toml", a), ("b.toml", b), ("c.toml", c)]),
+            vec!["c.toml: warning: requires pack \"z\", which isn't one of the packs checked"]
+        );
+    }
+
+    #[test]
+    fn test_broken_levels() {
+        let pack = pack(
+            "a",
+            &[
+                ("Bad Char", "#####\n#@$x#\n#####"),
+                ("No Player", "#####\n# $.#\n#####"),
+                ("Stuck", "######\n#.@$ #\n######"),
+            ],
+        );
+        assert_eq!(
+            messages(&[("a.toml", pack)]),
+            vec![
+                "a.toml: error: level \"Bad Char\": unexpected char 'x' at row 2, column 4",
+                "a.toml: error: level \"No Player\": needs one player and a goal for each crate",
+                "a.toml: error: level \"Stuck\": level can't be solved",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_par_below_fewest_pushes() {
+        let pack = pack("a", &[("Level 1", "######\n#@$ .#\n######")])
+            .replace("data", "par_pushes = 1\ndata");
+        assert_eq!(
+            messages(&[("a.toml", pack)]),
+            vec!["a.toml: error: level \"Level 1\": par_pushes is lower than the fewest pushes possible (2)"]
+        );
+    }
+}
@@ -2,18 +2,44 @@
 
 use macroquad::prelude::*;
 use miniquad::conf::Icon;
+use sokoworld::audio::AudioBus;
+use sokoworld::cli::{self, Command, Invocation};
 use sokoworld::consts::*;
 use sokoworld::context::Context;
+use sokoworld::input::touch::draw_button_bar;
+use sokoworld::input::Input;
 use sokoworld::level::pack::Pack;
 use sokoworld::scene::gameplay::Gameplay;
 use sokoworld::scene::level_select::LevelSelect;
-use sokoworld::scene::EScene;
-use sokoworld::scene::{main_menu::MainMenu, Scene};
+use sokoworld::scene::main_menu::MainMenu;
+use sokoworld::scene::{SceneChange, SceneStack};
 use sokoworld::text::{draw_text, Size};
+use sokoworld::toast::draw_toast;
+use sokoworld::transition::{Transition, TransitionKind};
+
+/// prints the error from the command line and exits, since the game can't start how it was asked
+fn exit_with_error(err: &str) -> ! {
+    eprintln!("error: {}\n\nRun with --help to see the options", err);
+    std::process::exit(2);
+}
 
 fn window_conf() -> Conf {
+    // args are handled before the window opens so that --help and errors don't flash one
+    let cli = match cli::init(&cli::GAME_OPTIONS) {
+        Ok(Invocation::Run(cli)) => cli,
+        Ok(Invocation::Help) => {
+            println!("{}", cli::usage());
+            std::process::exit(0);
+        }
+        Ok(Invocation::Version) => {
+            println!("{}", cli::version());
+            std::process::exit(0);
+        }
+        Err(err) => exit_with_error(&err),
+    };
+
     Conf {
-        fullscreen: false,
+        fullscreen: cli.fullscreen,
         high_dpi: true,
         icon: Some(Icon {
             small: include_bytes!("../icons/16x16.rgba").to_owned(),
@@ -38,27 +64,39 @@ async fn main() {
         ..Context::default().await
     };
 
-    let mut current_scene: Box<dyn Scene>;
+    ctx.input = Input::from_args(cli::args()).unwrap_or_else(|err| exit_with_error(&err));
 
-    // load pack & level from arg for quick testing, otherwise boot to main menu
-    let args: Vec<String> = std::env::args().collect();
-    if let Some(arg) = args.iter().find(|arg| arg.starts_with(PACK_CLI_ARG)) {
-        let pack_file = arg.split(PACK_CLI_ARG).last().unwrap();
-        let pack = Pack::load(&mut ctx, pack_file).await;
+    let mut scenes = SceneStack::new(Box::new(MainMenu::new(&ctx)));
+    let mut transition: Option<Transition> = None;
 
-        let mut level_index = 0;
-        if let Some(arg) = args.iter().find(|arg| arg.starts_with(LEVEL_CLI_ARG)) {
-            level_index = arg.split(LEVEL_CLI_ARG).last().unwrap().parse().unwrap();
-            level_index -= 1;
+    // jump into the pack from the play command for quick testing, otherwise boot to main menu
+    // the pack's level select is below the level, so finishing it goes back there
+    if let Some(Command::Play { pack, level }) = &cli::args().command {
+        let pack = load_pack_to_play(&ctx, pack)
+            .await
+            .unwrap_or_else(|err| exit_with_error(&err));
+        let level_index = match level {
+            Some(level) => level
+                .index_in(&pack)
+                .unwrap_or_else(|err| exit_with_error(&err)),
+            None => 0,
         };
-        let level = pack.levels.get(level_index).unwrap();
-        current_scene = Box::new(Gameplay::new(&mut ctx, level.clone(), level_index, pack).await);
-    } else {
-        current_scene = Box::new(MainMenu::new(&mut ctx).await);
-    };
+        let level = pack.levels[level_index].clone();
+        let level_select = LevelSelect::new(&ctx, pack.clone());
+        scenes.apply(SceneChange::Push(Box::new(level_select)));
+        let gameplay = Gameplay::new(&mut ctx, level, level_index, pack);
+        scenes.apply(SceneChange::Push(Box::new(gameplay)));
+    }
+
+    // closing the window goes through request_quit like quitting from the menus, so the save is
+    // written first
+    prevent_quit();
 
     loop {
         ///////// UPDATE
+        if is_quit_requested() {
+            ctx.request_quit = true;
+        }
         #[cfg(debug_assertions)]
         if (is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift))
             && is_key_down(KeyCode::Escape)
@@ -67,14 +105,43 @@ async fn main() {
         }
 
         ctx.gamepads.poll();
-        current_scene.update(&mut ctx);
+        if let Some(message) = ctx.input.begin_frame(
+            get_frame_time(),
+            &ctx.settings,
+            &ctx.gamepads,
+            ctx.input_layer,
+        ) {
+            ctx.toasts.push(message);
+        }
+        // the scenes wait while the screen is covered up so nothing is played unseen
+        if let Some(t) = transition.as_mut() {
+            for change in t.update(ctx.input.frame_time()) {
+                scenes.apply(change);
+            }
+            if t.is_done() {
+                transition = None;
+            }
+        } else {
+            scenes.update(&mut ctx);
+        }
+        ctx.toasts.update(ctx.input.frame_time());
+        let playlist = scenes.music();
+        let music_volume = ctx.settings.output_volume(AudioBus::Music);
+        ctx.audio
+            .music
+            .update(ctx.input.frame_time(), playlist, music_volume);
 
         ///////// DRAW
 
         // render target drawing
         set_camera(&ctx.render_target_cam);
         clear_background(sokoworld::color::DARKGRAY);
-        current_scene.draw(&mut ctx);
+        scenes.draw(&mut ctx);
+        draw_button_bar(&mut ctx);
+        if let Some(t) = transition.as_ref() {
+            t.draw();
+        }
+        draw_toast(&mut ctx);
 
         // regular drawing
         set_default_camera();
@@ -115,34 +182,78 @@ async fn main() {
         // nice if people want to design and test their own levels
         if ctx.reload_level {
             ctx.reload_level = false;
+            ctx.save.persist();
             if let Some(current_pack_file) = ctx.current_pack_file.clone() {
                 if let Some(current_level_index) = ctx.current_level_index {
-                    let pack = Pack::load(&mut ctx, current_pack_file.as_str()).await;
-                    let level = pack.levels.get(current_level_index).unwrap();
-                    current_scene = Box::new(
-                        Gameplay::new(&mut ctx, level.clone(), current_level_index, pack).await,
-                    );
+                    match Pack::load(current_pack_file.as_str()).await {
+                        Ok(pack) => match pack.levels.get(current_level_index) {
+                            Some(level) => {
+                                let gameplay = Gameplay::new(
+                                    &mut ctx,
+                                    level.clone(),
+                                    current_level_index,
+                                    pack,
+                                );
+                                ctx.set_scene_transition(TransitionKind::Cut);
+                                ctx.replace_scene(gameplay);
+                            }
+                            None => ctx.toasts.push(format!(
+                                "Reload failed: level {} isn't in the pack anymore",
+                                current_level_index + 1
+                            )),
+                        },
+                        // keep playing the level as it was so the pack can be fixed and reloaded
+                        Err(err) => ctx.toasts.push(format!("Reload failed: {}", err)),
+                    }
                 }
             }
         }
 
-        if let Some(escene) = ctx.switch_scene_to.clone() {
-            current_scene = match escene {
-                EScene::MainMenu => Box::new(MainMenu::new(&mut ctx).await),
-                EScene::LevelSelect(pack) => {
-                    Box::new(LevelSelect::new(&mut ctx, pack.clone()).await)
+        let scene_changes = std::mem::take(&mut ctx.scene_changes);
+        let requested_transition = ctx.scene_transition.take();
+        if !scene_changes.is_empty() {
+            // stats are recorded in memory while playing, so write them when leaving a scene
+            ctx.save.persist();
+
+            let mut kind =
+                requested_transition.unwrap_or_else(|| scenes.default_transition(&scene_changes));
+            if ctx.settings.is_reduced_motion() {
+                kind = kind.with_reduced_motion();
+            }
+            if kind == TransitionKind::Cut || transition.is_some() {
+                for change in scene_changes {
+                    scenes.apply(change);
                 }
-                EScene::Gameplay(level, level_index, pack) => Box::new(
-                    Gameplay::new(&mut ctx, level.clone(), level_index, pack.clone()).await,
-                ),
-            };
-            ctx.switch_scene_to = None;
+            } else {
+                transition = Some(Transition::new(kind, scene_changes));
+            }
         }
 
         next_frame().await;
 
         if ctx.request_quit {
+            ctx.save.persist();
             break;
         }
     }
 }
+
+/// loads the pack from its file if there's one at the path, otherwise finds the pack with the
+/// slug among the packs the game knows about
+async fn load_pack_to_play(ctx: &Context, pack: &str) -> Result<Pack, String> {
+    if std::path::Path::new(pack).is_file() {
+        return Pack::load(pack)
+            .await
+            .map_err(|err| format!("unable to load pack {}: {}", pack, err));
+    }
+
+    ctx.packs
+        .packs
+        .iter()
+        .find(|p| p.slug == pack)
+        .cloned()
+        .ok_or(format!(
+            "no pack file or pack with the slug \"{}\" was found",
+            pack
+        ))
+}
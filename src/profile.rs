@@ -0,0 +1,217 @@
+use crate::save::Save;
+use crate::storage::SharedStorage;
+use serde::{Deserialize, Serialize};
+
+/// the named players that share the game on one machine, each with their own save
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Profiles {
+    /// id of the profile currently being played
+    active: String,
+    profiles: Vec<Profile>,
+    /// used to generate unique ids for new profiles, never decremented
+    next_id: u32,
+    /// where the profiles and their saves are written
+    #[serde(skip)]
+    storage: Option<SharedStorage>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Profile {
+    /// id used for the save data location; NOTE: never changes, even on rename
+    pub id: String,
+    /// name shown in the menus
+    pub name: String,
+}
+
+/// id of the profile that existed before profiles were added; its save data lives where the
+/// single save always did so that existing progress carries over
+pub const DEFAULT_PROFILE_ID: &str = "default";
+/// how long a profile name can be
+pub const MAX_PROFILE_NAME_LEN: usize = 16;
+
+const PROFILES_FILE: &str = "profiles.ron";
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE_ID.to_string(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE_ID.to_string(),
+                name: "Player 1".to_string(),
+            }],
+            next_id: 2,
+            storage: None,
+        }
+    }
+}
+
+impl Profiles {
+    /// loads the profiles from storage; if they don't exist, instantiates the default and saves it
+    pub fn load(storage: SharedStorage) -> Self {
+        let stored = storage
+            .borrow()
+            .read(PROFILES_FILE)
+            .expect("couldn't read profiles file");
+        let mut profiles = match stored {
+            Some(ron_str) => ron::from_str(ron_str.as_str()).unwrap(),
+            None => Self::default(),
+        };
+
+        // guard against hand-edited files pointing to a profile that doesn't exist
+        if profiles.profiles.is_empty() {
+            profiles = Self::default();
+        }
+        if profiles.get(&profiles.active.clone()).is_none() {
+            profiles.active = profiles.profiles[0].id.clone();
+        }
+        profiles.storage = Some(storage);
+        profiles.save();
+
+        profiles
+    }
+
+    /// where the profiles and their saves are written
+    pub fn storage(&self) -> SharedStorage {
+        self.storage
+            .clone()
+            .expect("profiles not loaded from storage")
+    }
+
+    /// all of the profiles in the order they were created
+    pub fn all(&self) -> &Vec<Profile> {
+        &self.profiles
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.id == id)
+    }
+
+    /// the profile currently being played
+    pub fn active(&self) -> &Profile {
+        self.get(&self.active)
+            .expect("active profile not present in profiles")
+    }
+
+    /// makes the profile with the specified id the one being played
+    pub fn select(&mut self, id: &str) {
+        if self.get(id).is_some() {
+            self.active = id.to_string();
+            self.save();
+        }
+    }
+
+    /// adds a new profile with the specified name, returning its id
+    pub fn create(&mut self, name: &str) -> String {
+        let id = format!("profile-{}", self.next_id);
+        self.next_id += 1;
+        self.profiles.push(Profile {
+            id: id.clone(),
+            name: Self::clean_name(name),
+        });
+        self.save();
+        id
+    }
+
+    pub fn rename(&mut self, id: &str, name: &str) {
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.id == id) {
+            profile.name = Self::clean_name(name);
+            self.save();
+        }
+    }
+
+    /// removes the profile and its save data; the last remaining profile can't be deleted
+    ///
+    /// returns whether or not the profile was deleted
+    pub fn delete(&mut self, id: &str) -> bool {
+        if self.profiles.len() <= 1 || self.get(id).is_none() {
+            return false;
+        }
+
+        self.profiles.retain(|p| p.id != id);
+        if self.active == id {
+            self.active = self.profiles[0].id.clone();
+        }
+        if let Some(storage) = &self.storage {
+            Save::delete(storage, id);
+        }
+        self.save();
+        true
+    }
+
+    /// the name that's suggested when creating a new profile
+    pub fn next_default_name(&self) -> String {
+        format!("Player {}", self.profiles.len() + 1)
+    }
+
+    /// trims whitespace and caps the length, falling back to a placeholder if nothing is left
+    fn clean_name(name: &str) -> String {
+        let name: String = name.trim().chars().take(MAX_PROFILE_NAME_LEN).collect();
+        if name.is_empty() {
+            "Player".to_string()
+        } else {
+            name
+        }
+    }
+
+    /// writes the profiles to their storage, if they have one
+    fn save(&self) {
+        if let Some(storage) = &self.storage {
+            storage
+                .borrow_mut()
+                .write(PROFILES_FILE, &self.to_ron_string())
+                .expect("unable to write profiles file");
+        }
+    }
+
+    fn to_ron_string(&self) -> String {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{share, MemoryStorage};
+
+    #[test]
+    fn test_profiles_persist() {
+        let storage = share(MemoryStorage::default());
+        let mut profiles = Profiles::load(storage.clone());
+        let id = profiles.create("  Brett  ");
+        profiles.select(&id);
+
+        let profiles = Profiles::load(storage);
+        assert_eq!(profiles.all().len(), 2);
+        assert_eq!(profiles.active().id, id);
+        assert_eq!(profiles.active().name, "Brett");
+    }
+
+    #[test]
+    fn test_deleting_profile_removes_its_save() {
+        let storage = share(MemoryStorage::default());
+        let mut profiles = Profiles::load(storage.clone());
+        let id = profiles.create("Guest");
+        Save::load(storage.clone(), &id);
+        assert!(storage
+            .borrow()
+            .read("save-profile-2.ron")
+            .unwrap()
+            .is_some());
+
+        profiles.select(&id);
+        assert!(profiles.delete(&id));
+        assert!(storage
+            .borrow()
+            .read("save-profile-2.ron")
+            .unwrap()
+            .is_none());
+        assert_eq!(profiles.active().id, DEFAULT_PROFILE_ID);
+    }
+
+    #[test]
+    fn test_last_profile_cant_be_deleted() {
+        let mut profiles = Profiles::load(share(MemoryStorage::default()));
+        assert!(!profiles.delete(DEFAULT_PROFILE_ID));
+        assert_eq!(profiles.all().len(), 1);
+    }
+}
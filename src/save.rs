@@ -1,11 +1,15 @@
 #[cfg(not(target_family = "wasm"))]
 use crate::fs;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 #[cfg(not(target_family = "wasm"))]
 use std::path::PathBuf;
 
 use crate::consts::VERSION;
+use crate::level::pack::Medal;
+use crate::profile::DEFAULT_PROFILE_ID;
+use crate::storage::SharedStorage;
 
 /// game completion progress
 #[derive(Debug, Deserialize, Serialize)]
@@ -13,117 +17,419 @@ pub struct Save {
     game_version: String,
     /// string key is PACKSLUG:LEVELNAME
     level_completions: HashMap<String, LevelCompletion>,
+    /// lifetime totals, recorded while playing
+    #[serde(default)]
+    stats: Stats,
+    /// ids of the achievements that have been unlocked, in the order they were unlocked
+    #[serde(default)]
+    achievements: Vec<String>,
+    /// which profile the save belongs to, determines where it's written
+    #[serde(skip)]
+    profile_id: String,
+    /// where the save is written; saves that aren't loaded from storage, like imported ones,
+    /// are never written
+    #[serde(skip)]
+    storage: Option<SharedStorage>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 struct LevelCompletion {
     pack: String,
     level: String,
     steps: i32,
     pushes: i32,
+    /// how many times the level has been completed
+    #[serde(default)]
+    times_completed: u32,
+    /// best medal earned across every completion, if the level has a par
+    #[serde(default)]
+    medal: Option<Medal>,
+}
+
+/// lifetime totals for a profile across every level played
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Stats {
+    /// every step taken, including ones that were later rewound
+    pub steps: u64,
+    /// every crate push, including ones that were later rewound
+    pub pushes: u64,
+    /// seconds spent playing levels, not counting time paused
+    pub play_time: f64,
+    /// string key is PACKSLUG:LEVELNAME
+    pub levels: HashMap<String, LevelStats>,
+}
+
+/// totals for a single level
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LevelStats {
+    pub pack: String,
+    pub level: String,
+    /// how many times the level was started or reset
+    pub attempts: u32,
+    /// seconds spent playing the level, not counting time paused
+    pub play_time: f64,
+}
+
+/// what was brought in from another save when merging
+#[derive(Debug, Default, PartialEq)]
+pub struct Merged {
+    /// levels that were added or improved
+    pub levels: usize,
+    /// achievements unlocked in the other save but not this one
+    pub achievements: usize,
+    /// whether any of the stats went up
+    pub stats: bool,
 }
 
+impl Stats {
+    /// keeps the larger of each total, since the saves may have been copied from each other and
+    /// adding them would count the same play twice; returns whether anything changed
+    fn merge(&mut self, other: &Stats) -> bool {
+        let mut changed = false;
+        let mut keep_larger = |ours: &mut u64, theirs: u64| {
+            if theirs > *ours {
+                *ours = theirs;
+                changed = true;
+            }
+        };
+        keep_larger(&mut self.steps, other.steps);
+        keep_larger(&mut self.pushes, other.pushes);
+
+        if other.play_time > self.play_time {
+            self.play_time = other.play_time;
+            changed = true;
+        }
+        for (key, theirs) in &other.levels {
+            let ours = self
+                .levels
+                .entry(key.clone())
+                .or_insert_with(|| LevelStats {
+                    pack: theirs.pack.clone(),
+                    level: theirs.level.clone(),
+                    ..Default::default()
+                });
+            if theirs.attempts > ours.attempts {
+                ours.attempts = theirs.attempts;
+                changed = true;
+            }
+            if theirs.play_time > ours.play_time {
+                ours.play_time = theirs.play_time;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+const SAVE_FILE: &str = "save";
+const SAVE_FILE_EXT: &str = "ron";
+
+/// file that progress is exported to, in the same dir as the save
 #[cfg(not(target_family = "wasm"))]
-const SAVE_FILE: &str = "save.ron";
+const EXPORT_FILE: &str = "sokoworld-progress.txt";
 
-#[cfg(target_family = "wasm")]
-const WASM_SAVE_KEY: &str = "save";
+/// file that progress from another computer is imported from, kept apart from the export file so
+/// importing doesn't read back what was just exported
+#[cfg(not(target_family = "wasm"))]
+const IMPORT_FILE: &str = "sokoworld-import.txt";
+
+/// prefix for progress codes, bump the number if the format ever changes
+const PROGRESS_CODE_PREFIX: &str = "SOKO1:";
 
 impl Default for Save {
     fn default() -> Self {
         Self {
             game_version: VERSION.to_string(),
             level_completions: HashMap::new(),
+            stats: Stats::default(),
+            achievements: vec![],
+            profile_id: DEFAULT_PROFILE_ID.to_string(),
+            storage: None,
         }
     }
 }
 
 impl Save {
-    /// loads the save file from disk; if it doesn't exist, instantiates a new one and saves it
-    pub fn load() -> Self {
-        #[cfg(target_family = "wasm")]
-        let save = Self::load_wasm();
-
-        #[cfg(not(target_family = "wasm"))]
-        let save = Self::load_desktop();
+    /// loads the save for the profile from storage; if it doesn't exist, instantiates a new one
+    /// and saves it
+    pub fn load(storage: SharedStorage, profile_id: &str) -> Self {
+        let stored = storage
+            .borrow()
+            .read(&Self::storage_key(profile_id))
+            .expect("couldn't read save file");
+        let mut save = match stored {
+            Some(ron_str) => ron::from_str(ron_str.as_str()).unwrap(),
+            None => Self::default(),
+        };
+        save.profile_id = profile_id.to_string();
+        save.storage = Some(storage);
         save.save();
 
         save
     }
 
-    #[cfg(not(target_family = "wasm"))]
-    fn load_desktop() -> Self {
-        let save_path = Self::determine_save_path();
+    /// removes the save data for the profile, used when deleting a profile
+    pub fn delete(storage: &SharedStorage, profile_id: &str) {
+        storage
+            .borrow_mut()
+            .remove(&Self::storage_key(profile_id))
+            .expect("unable to delete save file");
+    }
 
-        if save_path.exists() {
-            let toml_str = std::fs::read_to_string(save_path).expect("couldn't read save file");
-            let save: Save = ron::from_str(toml_str.as_str()).unwrap();
-            save
+    /// the default profile uses `save.ron` so saves from before profiles existed still load,
+    /// other profiles use `save-PROFILEID.ron`
+    fn storage_key(profile_id: &str) -> String {
+        if profile_id == DEFAULT_PROFILE_ID {
+            format!("{}.{}", SAVE_FILE, SAVE_FILE_EXT)
         } else {
-            Self::default()
+            format!("{}-{}.{}", SAVE_FILE, profile_id, SAVE_FILE_EXT)
         }
     }
 
-    #[cfg(not(target_family = "wasm"))]
-    fn determine_save_path() -> PathBuf {
-        let project_dirs = fs::project_dirs();
-        let save_dir = project_dirs.data_local_dir();
-        std::fs::create_dir_all(save_dir).unwrap();
-        let mut save_path = PathBuf::from(save_dir);
-        save_path.push(SAVE_FILE);
-        save_path
-    }
-
-    #[cfg(target_family = "wasm")]
-    fn load_wasm() -> Self {
-        let mut save = Self::default();
-        let storage = &mut quad_storage::STORAGE.lock().unwrap();
-        if let Some(wasm_save) = storage.get(WASM_SAVE_KEY) {
-            save = ron::from_str(wasm_save.as_str()).unwrap();
-        }
-        save
-    }
-
     pub fn complete_level(
         &mut self,
         pack_slug: String,
         level_title: String,
         steps: i32,
         pushes: i32,
+        medal: Option<Medal>,
     ) {
+        let key = Self::level_completion_key(&pack_slug, &level_title);
+        let (times_completed, best_medal) = self
+            .level_completions
+            .get(&key)
+            .map_or((0, None), |c| (c.times_completed, c.medal));
         self.level_completions.insert(
-            Self::level_completion_key(&pack_slug, &level_title),
+            key,
             LevelCompletion {
                 pack: pack_slug,
                 level: level_title,
                 steps,
                 pushes,
+                times_completed: times_completed + 1,
+                medal: medal.max(best_medal),
             },
         );
         self.save();
     }
 
+    /// counts a start or reset of the level, doesn't write the save
+    pub fn record_attempt(&mut self, pack_slug: &String, level_title: &String) {
+        self.level_stats_mut(pack_slug, level_title).attempts += 1;
+    }
+
+    /// counts a step taken by the player, doesn't write the save
+    pub fn record_step(&mut self, pushed_crate: bool) {
+        self.stats.steps += 1;
+        if pushed_crate {
+            self.stats.pushes += 1;
+        }
+    }
+
+    /// adds the seconds to the total time played, doesn't write the save
+    pub fn record_play_time(&mut self, pack_slug: &String, level_title: &String, seconds: f32) {
+        self.stats.play_time += seconds as f64;
+        self.level_stats_mut(pack_slug, level_title).play_time += seconds as f64;
+    }
+
+    fn level_stats_mut(&mut self, pack_slug: &String, level_title: &String) -> &mut LevelStats {
+        self.stats
+            .levels
+            .entry(Self::level_completion_key(pack_slug, level_title))
+            .or_insert_with(|| LevelStats {
+                pack: pack_slug.clone(),
+                level: level_title.clone(),
+                ..Default::default()
+            })
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// the levels that have been attempted the most, most attempted first
+    pub fn most_retried_levels(&self, count: usize) -> Vec<&LevelStats> {
+        let mut levels: Vec<&LevelStats> = self
+            .stats
+            .levels
+            .values()
+            .filter(|l| l.attempts > 1)
+            .collect();
+        levels.sort_by(|a, b| b.attempts.cmp(&a.attempts).then(a.level.cmp(&b.level)));
+        levels.truncate(count);
+        levels
+    }
+
+    /// writes the save so that recorded stats aren't lost, call when leaving a level
+    pub fn persist(&self) {
+        self.save();
+    }
+
     pub fn is_level_complete(&self, pack_slug: &String, level_title: &String) -> bool {
         self.level_completions
             .contains_key(&Self::level_completion_key(pack_slug, level_title))
     }
 
-    fn level_completion_key(pack_slug: &String, level_title: &String) -> String {
-        format!("{}:{}", pack_slug, level_title)
+    /// the best medal earned for the level, if any
+    pub fn level_medal(&self, pack_slug: &String, level_title: &String) -> Option<Medal> {
+        self.level_completions
+            .get(&Self::level_completion_key(pack_slug, level_title))
+            .and_then(|c| c.medal)
     }
 
-    /// writes the save to local storage
-    #[cfg(target_family = "wasm")]
-    fn save(&self) {
-        let storage = &mut quad_storage::STORAGE.lock().unwrap();
-        storage.set(WASM_SAVE_KEY, &self.to_ron_string().as_str());
+    /// how many different levels have been completed across all packs
+    pub fn completed_level_count(&self) -> usize {
+        self.level_completions.len()
+    }
+
+    /// how many different levels have been completed in the pack with the slug
+    pub fn completed_level_count_in_pack(&self, pack_slug: &str) -> usize {
+        self.level_completions
+            .values()
+            .filter(|c| c.pack == pack_slug)
+            .count()
+    }
+
+    pub fn is_achievement_unlocked(&self, achievement_id: &str) -> bool {
+        self.achievements.iter().any(|id| id == achievement_id)
+    }
+
+    /// marks the achievement as unlocked and writes the save
+    pub fn unlock_achievement(&mut self, achievement_id: &str) {
+        if !self.is_achievement_unlocked(achievement_id) {
+            self.achievements.push(achievement_id.to_string());
+            self.save();
+        }
+    }
+
+    /// encodes all of the level completions as a single line of text that can be copied between
+    /// the desktop and web builds
+    pub fn to_progress_code(&self) -> String {
+        let ron = ron::to_string(self).unwrap();
+        format!("{}{}", PROGRESS_CODE_PREFIX, URL_SAFE_NO_PAD.encode(ron))
+    }
+
+    /// decodes a save from a progress code, whitespace around the code is ignored
+    pub fn from_progress_code(code: &str) -> Result<Self, String> {
+        let encoded = code
+            .trim()
+            .strip_prefix(PROGRESS_CODE_PREFIX)
+            .ok_or("not a SokoWorld progress code")?;
+        let bytes = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|e| format!("progress code is damaged: {}", e))?;
+        let ron = String::from_utf8(bytes).map_err(|_| "progress code is damaged".to_string())?;
+        ron::from_str(ron.as_str()).map_err(|e| format!("progress code is invalid: {}", e))
+    }
+
+    /// combines the other save into this one, keeping the best result for each level (fewest
+    /// steps, then fewest pushes) along with the best medal, every unlocked achievement and the
+    /// larger of each stat, and then writes it
+    pub fn merge(&mut self, other: &Save) -> Merged {
+        let merged = Merged {
+            levels: self.merge_level_completions(other),
+            achievements: self.merge_achievements(other),
+            stats: self.stats.merge(&other.stats),
+        };
+        if merged != Merged::default() {
+            self.save();
+        }
+        merged
+    }
+
+    /// adds the other save's achievements that aren't unlocked here, returning how many
+    fn merge_achievements(&mut self, other: &Save) -> usize {
+        let mut added = 0;
+        for id in &other.achievements {
+            if !self.is_achievement_unlocked(id) {
+                self.achievements.push(id.clone());
+                added += 1;
+            }
+        }
+        added
+    }
+
+    fn merge_level_completions(&mut self, other: &Save) -> usize {
+        let mut changed = 0;
+        for (key, theirs) in &other.level_completions {
+            match self.level_completions.get_mut(key) {
+                Some(ours) => {
+                    let before = (ours.steps, ours.pushes, ours.times_completed, ours.medal);
+                    if (theirs.steps, theirs.pushes) < (ours.steps, ours.pushes) {
+                        ours.steps = theirs.steps;
+                        ours.pushes = theirs.pushes;
+                    }
+                    // the saves may have been copied from each other, so the counts aren't added
+                    ours.times_completed = ours.times_completed.max(theirs.times_completed);
+                    ours.medal = ours.medal.max(theirs.medal);
+                    if before == (ours.steps, ours.pushes, ours.times_completed, ours.medal) {
+                        continue;
+                    }
+                }
+                None => {
+                    self.level_completions.insert(key.clone(), theirs.clone());
+                }
+            }
+            changed += 1;
+        }
+        changed
+    }
+
+    /// where progress gets exported to on desktop
+    #[cfg(not(target_family = "wasm"))]
+    pub fn determine_export_path() -> PathBuf {
+        let mut export_path = fs::data_dir();
+        export_path.push(EXPORT_FILE);
+        export_path
+    }
+
+    /// where progress gets imported from on desktop
+    #[cfg(not(target_family = "wasm"))]
+    pub fn determine_import_path() -> PathBuf {
+        let mut import_path = fs::data_dir();
+        import_path.push(IMPORT_FILE);
+        import_path
+    }
+
+    /// writes the progress code to the export file, returning where it was written
+    #[cfg(not(target_family = "wasm"))]
+    pub fn export_to_file(&self) -> Result<PathBuf, String> {
+        let export_path = Self::determine_export_path();
+        std::fs::write(&export_path, self.to_progress_code())
+            .map_err(|e| format!("unable to write {}: {}", export_path.display(), e))?;
+        Ok(export_path)
+    }
+
+    /// reads a save from the import file, which can hold a progress code or a `save.ron`, then
+    /// removes the file so the next import reads the clipboard instead
+    #[cfg(not(target_family = "wasm"))]
+    pub fn import_from_file() -> Result<Self, String> {
+        Self::import_from(&Self::determine_import_path())
     }
 
     #[cfg(not(target_family = "wasm"))]
-    /// writes the save to disk
+    fn import_from(import_path: &std::path::Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(import_path)
+            .map_err(|e| format!("unable to read {}: {}", import_path.display(), e))?;
+        let save = Self::from_progress_code(&contents)
+            .or_else(|code_err| ron::from_str(contents.as_str()).map_err(|_| code_err))?;
+        std::fs::remove_file(import_path)
+            .map_err(|e| format!("unable to remove {}: {}", import_path.display(), e))?;
+        Ok(save)
+    }
+
+    fn level_completion_key(pack_slug: &String, level_title: &String) -> String {
+        format!("{}:{}", pack_slug, level_title)
+    }
+
+    /// writes the save to its storage, if it has one
     fn save(&self) {
-        std::fs::write(Self::determine_save_path(), self.to_ron_string())
-            .expect("unable to write save file");
+        if let Some(storage) = &self.storage {
+            storage
+                .borrow_mut()
+                .write(&Self::storage_key(&self.profile_id), &self.to_ron_string())
+                .expect("unable to write save file");
+        }
     }
 
     /// returns the save data in RON format as a pretty string
@@ -131,3 +437,180 @@ impl Save {
         ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save_with(completions: Vec<(&str, &str, i32, i32)>) -> Save {
+        let mut save = Save::default();
+        for (pack, level, steps, pushes) in completions {
+            save.level_completions.insert(
+                Save::level_completion_key(&pack.to_string(), &level.to_string()),
+                LevelCompletion {
+                    pack: pack.to_string(),
+                    level: level.to_string(),
+                    steps,
+                    pushes,
+                    times_completed: 1,
+                    medal: None,
+                },
+            );
+        }
+        save
+    }
+
+    #[test]
+    fn test_most_retried_levels() {
+        let mut save = Save::default();
+        let pack = "pack-a".to_string();
+        for (level, attempts) in [("Level 1", 1), ("Level 2", 4), ("Level 3", 2)] {
+            for _ in 0..attempts {
+                save.record_attempt(&pack, &level.to_string());
+            }
+        }
+
+        let most_retried: Vec<&str> = save
+            .most_retried_levels(5)
+            .iter()
+            .map(|l| l.level.as_str())
+            .collect();
+        assert_eq!(most_retried, vec!["Level 2", "Level 3"]);
+    }
+
+    #[test]
+    fn test_record_steps_and_play_time() {
+        let mut save = Save::default();
+        save.record_step(false);
+        save.record_step(true);
+        save.record_play_time(&"pack-a".to_string(), &"Level 1".to_string(), 1.5);
+
+        assert_eq!(save.stats().steps, 2);
+        assert_eq!(save.stats().pushes, 1);
+        assert_eq!(save.stats().play_time, 1.5);
+        assert_eq!(
+            save.stats().levels.get("pack-a:Level 1").unwrap().play_time,
+            1.5
+        );
+    }
+
+    #[test]
+    fn test_progress_code_round_trip() {
+        let save = save_with(vec![("pack-a", "Level 1", 3, 1)]);
+        let code = save.to_progress_code();
+        assert!(code.starts_with(PROGRESS_CODE_PREFIX));
+        assert!(!code.contains('\n'));
+
+        let decoded = Save::from_progress_code(&format!("  {}\n", code)).unwrap();
+        assert!(decoded.is_level_complete(&"pack-a".to_string(), &"Level 1".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_progress_code() {
+        assert!(Save::from_progress_code("hello").is_err());
+        assert!(Save::from_progress_code("SOKO1:!!!").is_err());
+    }
+
+    #[test]
+    fn test_merge_keeps_best_result() {
+        let mut ours = save_with(vec![
+            ("pack-a", "Level 1", 10, 2),
+            ("pack-a", "Level 2", 5, 1),
+        ]);
+        ours.level_completions
+            .get_mut("pack-a:Level 1")
+            .unwrap()
+            .times_completed = 5;
+        let theirs = save_with(vec![
+            ("pack-a", "Level 1", 8, 3),
+            ("pack-a", "Level 2", 5, 2),
+            ("pack-a", "Level 3", 20, 4),
+        ]);
+        assert_eq!(ours.merge_level_completions(&theirs), 2);
+
+        let level_1 = ours.level_completions.get("pack-a:Level 1").unwrap();
+        assert_eq!((level_1.steps, level_1.pushes), (8, 3));
+        assert_eq!(level_1.times_completed, 5);
+        let level_2 = ours.level_completions.get("pack-a:Level 2").unwrap();
+        assert_eq!((level_2.steps, level_2.pushes), (5, 1));
+        assert!(ours.is_level_complete(&"pack-a".to_string(), &"Level 3".to_string()));
+    }
+
+    #[test]
+    fn test_complete_level_keeps_best_medal() {
+        let mut save = Save::default();
+        let (pack, level) = ("pack-a".to_string(), "Level 1".to_string());
+        save.complete_level(pack.clone(), level.clone(), 10, 4, Some(Medal::Silver));
+        save.complete_level(pack.clone(), level.clone(), 30, 8, Some(Medal::Bronze));
+        assert_eq!(save.level_medal(&pack, &level), Some(Medal::Silver));
+        save.complete_level(pack.clone(), level.clone(), 8, 3, Some(Medal::Gold));
+        assert_eq!(save.level_medal(&pack, &level), Some(Medal::Gold));
+    }
+
+    #[test]
+    fn test_merge_keeps_best_medal() {
+        let mut ours = save_with(vec![("pack-a", "Level 1", 8, 3)]);
+        ours.level_completions
+            .get_mut("pack-a:Level 1")
+            .unwrap()
+            .medal = Some(Medal::Silver);
+        let mut theirs = save_with(vec![("pack-a", "Level 1", 10, 3)]);
+        theirs
+            .level_completions
+            .get_mut("pack-a:Level 1")
+            .unwrap()
+            .medal = Some(Medal::Gold);
+        assert_eq!(ours.merge_level_completions(&theirs), 1);
+
+        let level_1 = ours.level_completions.get("pack-a:Level 1").unwrap();
+        assert_eq!((level_1.steps, level_1.pushes), (8, 3));
+        assert_eq!(level_1.medal, Some(Medal::Gold));
+        assert_eq!(ours.merge_level_completions(&theirs), 0);
+    }
+
+    #[test]
+    fn test_merge_achievements_and_stats() {
+        let mut ours = Save {
+            achievements: vec!["first-level".to_string()],
+            ..Default::default()
+        };
+        ours.record_step(true);
+        ours.record_attempt(&"pack-a".to_string(), &"Level 1".to_string());
+        let mut theirs = Save {
+            achievements: vec!["no-rewind".to_string(), "first-level".to_string()],
+            ..Default::default()
+        };
+        for _ in 0..3 {
+            theirs.record_step(false);
+        }
+        theirs.record_attempt(&"pack-a".to_string(), &"Level 2".to_string());
+
+        let merged = ours.merge(&theirs);
+        assert_eq!(
+            merged,
+            Merged {
+                levels: 0,
+                achievements: 1,
+                stats: true
+            }
+        );
+        assert_eq!(ours.achievements, vec!["first-level", "no-rewind"]);
+        assert_eq!((ours.stats.steps, ours.stats.pushes), (3, 1));
+        assert_eq!(ours.stats.levels.len(), 2);
+        assert_eq!(ours.merge(&theirs), Merged::default());
+    }
+
+    #[test]
+    #[cfg(not(target_family = "wasm"))]
+    fn test_import_removes_the_import_file() {
+        let path =
+            std::env::temp_dir().join(format!("sokoworld-import-{}.txt", std::process::id()));
+        let save = save_with(vec![("pack-a", "Level 1", 8, 3)]);
+        std::fs::write(&path, save.to_progress_code()).unwrap();
+
+        let imported = Save::import_from(&path).unwrap();
+        assert!(imported.level_completions.contains_key("pack-a:Level 1"));
+        assert!(!path.exists());
+        assert!(Save::import_from(&path).is_err());
+    }
+}
@@ -1,27 +1,231 @@
-/// enum of various scenes that exist
-// not sure if there's a better way to do this...
-#[derive(Clone, Debug)]
-pub enum EScene {
-    Gameplay(PackLevel, usize, Pack),
-    MainMenu,
-    LevelSelect(Pack),
-    // HowToPlay,
-    // Credits,
-}
-
-use crate::{
-    context::Context,
-    level::pack::{Pack, PackLevel},
-};
+use crate::context::Context;
+use crate::input::InputLayer;
+use crate::transition::TransitionKind;
 
+pub mod achievements;
+pub mod audio_settings;
+pub mod broken_packs;
+pub mod controls;
 pub mod credits;
 pub mod gameplay;
 pub mod level_select;
 pub mod main_menu;
 pub mod pause;
+pub mod profiles;
 pub mod settings;
+pub mod stats;
 
 pub trait Scene {
     fn update(&mut self, ctx: &mut Context);
     fn draw(&mut self, ctx: &mut Context);
+
+    /// whether the scenes below this one are drawn first, like the level behind the pause menu
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    /// the bindings checked while this scene is updated
+    fn input_layer(&self) -> InputLayer {
+        InputLayer::Menu
+    }
+
+    /// the music playlist played while this scene is on top, or none to keep playing the one
+    /// from the scenes below, like the pause menu over a level
+    fn music(&self) -> Option<&str> {
+        None
+    }
+
+    /// called when the scene is back on top after the ones above it were popped
+    fn resume(&mut self, _ctx: &mut Context) {}
+}
+
+/// a change to the scene stack that a scene asked for, made once the frame is done so the
+/// scene isn't replaced while it's being updated
+pub enum SceneChange {
+    /// puts the scene on top, like opening settings from the main menu
+    Push(Box<dyn Scene>),
+    /// removes the top scene, going back to the one below
+    Pop,
+    /// removes the top scene and puts this one in its place
+    Replace(Box<dyn Scene>),
+    /// removes every scene and starts over with this one, like returning to the main menu
+    Reset(Box<dyn Scene>),
+}
+
+/// the scenes that are open, where only the one on top is updated
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    /// whether scenes were popped since the last update, so the top one needs resuming
+    resume_top: bool,
+}
+
+impl SceneStack {
+    pub fn new(scene: Box<dyn Scene>) -> Self {
+        Self {
+            scenes: vec![scene],
+            resume_top: false,
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut Context) {
+        if let Some(scene) = self.scenes.last_mut() {
+            if self.resume_top {
+                self.resume_top = false;
+                scene.resume(ctx);
+            }
+            ctx.input_layer = scene.input_layer();
+            scene.update(ctx);
+        }
+    }
+
+    /// draws the top scene, along with the scenes below it that it's an overlay for
+    pub fn draw(&mut self, ctx: &mut Context) {
+        let first_drawn = self.first_drawn_index();
+        for scene in &mut self.scenes[first_drawn..] {
+            scene.draw(ctx);
+        }
+    }
+
+    /// the transition to use for the changes when the scene that asked for them didn't pick
+    /// one; overlays, like the pause menu, show up right away
+    pub fn default_transition(&self, changes: &[SceneChange]) -> TransitionKind {
+        let mut top = self.scenes.len();
+        for change in changes {
+            match change {
+                SceneChange::Push(scene) if !scene.is_overlay() => return TransitionKind::Slide,
+                SceneChange::Push(_) => {}
+                SceneChange::Pop if top > 1 => {
+                    top -= 1;
+                    if !self.scenes[top].is_overlay() {
+                        return TransitionKind::Slide;
+                    }
+                }
+                SceneChange::Pop => {}
+                SceneChange::Replace(_) | SceneChange::Reset(_) => return TransitionKind::Fade,
+            }
+        }
+        TransitionKind::Cut
+    }
+
+    /// the playlist asked for by the top scene that picks one
+    pub fn music(&self) -> Option<&str> {
+        self.scenes.iter().rev().find_map(|scene| scene.music())
+    }
+
+    /// index of the top scene that isn't an overlay, which is drawn first
+    fn first_drawn_index(&self) -> usize {
+        self.scenes
+            .iter()
+            .rposition(|s| !s.is_overlay())
+            .unwrap_or(0)
+    }
+
+    /// makes the change, resuming the new top scene on its next update when scenes were popped;
+    /// the last scene is never popped so there's always one to show
+    pub fn apply(&mut self, change: SceneChange) {
+        match change {
+            SceneChange::Push(scene) => self.scenes.push(scene),
+            SceneChange::Pop => {
+                if self.scenes.len() > 1 {
+                    self.scenes.pop();
+                    self.resume_top = true;
+                }
+            }
+            SceneChange::Replace(scene) => {
+                self.scenes.pop();
+                self.scenes.push(scene);
+            }
+            SceneChange::Reset(scene) => {
+                self.scenes = vec![scene];
+                self.resume_top = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestScene {
+        overlay: bool,
+    }
+
+    impl Scene for TestScene {
+        fn update(&mut self, _ctx: &mut Context) {}
+        fn draw(&mut self, _ctx: &mut Context) {}
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    fn scene(overlay: bool) -> Box<dyn Scene> {
+        Box::new(TestScene { overlay })
+    }
+
+    #[test]
+    fn test_push_pop_and_reset() {
+        let mut stack = SceneStack::new(scene(false));
+        stack.apply(SceneChange::Push(scene(false)));
+        stack.apply(SceneChange::Push(scene(false)));
+        assert_eq!(stack.scenes.len(), 3);
+
+        stack.apply(SceneChange::Pop);
+        assert_eq!(stack.scenes.len(), 2);
+        assert!(stack.resume_top);
+
+        stack.apply(SceneChange::Replace(scene(false)));
+        assert_eq!(stack.scenes.len(), 2);
+
+        stack.apply(SceneChange::Reset(scene(false)));
+        assert_eq!(stack.scenes.len(), 1);
+        assert!(!stack.resume_top);
+
+        // there's always a scene to show
+        stack.apply(SceneChange::Pop);
+        assert_eq!(stack.scenes.len(), 1);
+    }
+
+    #[test]
+    fn test_overlays_draw_the_scenes_below() {
+        let mut stack = SceneStack::new(scene(false));
+        stack.apply(SceneChange::Push(scene(false)));
+        assert_eq!(stack.first_drawn_index(), 1);
+
+        stack.apply(SceneChange::Push(scene(true)));
+        stack.apply(SceneChange::Push(scene(true)));
+        assert_eq!(stack.first_drawn_index(), 1);
+
+        stack.apply(SceneChange::Push(scene(false)));
+        assert_eq!(stack.first_drawn_index(), 4);
+    }
+
+    #[test]
+    fn test_default_transition() {
+        let mut stack = SceneStack::new(scene(false));
+        stack.apply(SceneChange::Push(scene(false)));
+        assert_eq!(
+            stack.default_transition(&[SceneChange::Push(scene(true))]),
+            TransitionKind::Cut
+        );
+        assert_eq!(
+            stack.default_transition(&[SceneChange::Push(scene(false))]),
+            TransitionKind::Slide
+        );
+        assert_eq!(
+            stack.default_transition(&[SceneChange::Reset(scene(false))]),
+            TransitionKind::Fade
+        );
+
+        // closing the pause menu is a cut, but closing the level below it too slides
+        stack.apply(SceneChange::Push(scene(true)));
+        assert_eq!(
+            stack.default_transition(&[SceneChange::Pop]),
+            TransitionKind::Cut
+        );
+        assert_eq!(
+            stack.default_transition(&[SceneChange::Pop, SceneChange::Pop]),
+            TransitionKind::Slide
+        );
+    }
 }
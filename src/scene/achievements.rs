@@ -0,0 +1,103 @@
+use macroquad::color::{RED, WHITE};
+
+use super::Scene;
+use crate::audio::{play_sfx, Sfx};
+use crate::color::{BLUE, GRAY};
+use crate::consts::{TITLE_Y_INSET, VIRTUAL_HEIGHT, X_INSET};
+use crate::input::{action_pressed, action_repeated, Action, InputLayer};
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// how many achievements fit on screen at once
+const VISIBLE_COUNT: usize = 7;
+const ROW_HEIGHT: f32 = 60.;
+
+/// scene listing every achievement and whether or not it's been unlocked
+pub struct Achievements {
+    /// index of the first achievement shown, for scrolling
+    scroll_index: usize,
+}
+
+impl Achievements {
+    pub fn new(_ctx: &Context) -> Self {
+        Self { scroll_index: 0 }
+    }
+}
+
+impl Scene for Achievements {
+    fn update(&mut self, ctx: &mut Context) {
+        if action_pressed(Action::Cancel, ctx) || action_pressed(Action::Confirm, ctx) {
+            play_sfx(ctx, Sfx::MenuCancel);
+            ctx.pop_scene();
+            return;
+        }
+
+        let max_scroll_index = ctx
+            .achievements
+            .achievements
+            .len()
+            .saturating_sub(VISIBLE_COUNT);
+
+        if action_repeated(Action::Up, ctx) && self.scroll_index > 0 {
+            play_sfx(ctx, Sfx::MenuMove);
+            self.scroll_index -= 1;
+        }
+        if action_repeated(Action::Down, ctx) && self.scroll_index < max_scroll_index {
+            play_sfx(ctx, Sfx::MenuMove);
+            self.scroll_index += 1;
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        let achievements = ctx.achievements.achievements.clone();
+        let unlocked_count = achievements
+            .iter()
+            .filter(|a| ctx.save.is_achievement_unlocked(&a.id))
+            .count();
+
+        let title = format!("Achievements ({} / {})", unlocked_count, achievements.len());
+        draw_text(ctx, &title, X_INSET, TITLE_Y_INSET, Size::Large, WHITE);
+
+        for (i, achievement) in achievements
+            .iter()
+            .skip(self.scroll_index)
+            .take(VISIBLE_COUNT)
+            .enumerate()
+        {
+            let unlocked = ctx.save.is_achievement_unlocked(&achievement.id);
+            let color = if unlocked { BLUE } else { GRAY };
+            let y = 190. + i as f32 * ROW_HEIGHT;
+            let title = if unlocked {
+                achievement.title.clone()
+            } else {
+                format!("{} (locked)", achievement.title)
+            };
+
+            draw_text(ctx, &title, X_INSET, y, Size::Medium, color);
+            draw_text(
+                ctx,
+                &achievement.description,
+                X_INSET,
+                y + 24.,
+                Size::Small,
+                color,
+            );
+        }
+
+        let controls = ctx.settings.controls();
+        let hint = format!(
+            "Scroll = {} | Return = {} or {}",
+            controls.directions_hint(InputLayer::Menu, &[Action::Up, Action::Down]),
+            controls.hint(InputLayer::Menu, Action::Confirm),
+            controls.hint(InputLayer::Menu, Action::Cancel)
+        );
+        draw_text(
+            ctx,
+            hint.as_str(),
+            X_INSET,
+            VIRTUAL_HEIGHT - 40.,
+            Size::Small,
+            RED,
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use macroquad::color::{RED, WHITE};
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
+
+use super::Scene;
+use crate::audio::{play_sfx, AudioBus, Sfx};
+use crate::color::{BLUE, DARKGRAY};
+use crate::consts::{VIRTUAL_HEIGHT, X_INSET};
+use crate::input::{action_pressed, action_repeated, Action};
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+const SLIDER_X: f32 = X_INSET + 480.;
+const SLIDER_WIDTH: f32 = 300.;
+const SLIDER_HEIGHT: f32 = 20.;
+
+/// scene for muting and setting the volume of each audio bus, opened from settings
+pub struct AudioSettings {
+    menu_options: Vec<MenuOption>,
+    menu_index: usize,
+}
+
+enum MenuOption {
+    Mute,
+    Volume(AudioBus),
+    Back,
+}
+
+impl AudioSettings {
+    pub fn new(_ctx: &Context) -> Self {
+        let menu_options = std::iter::once(MenuOption::Mute)
+            .chain(AudioBus::ALL.into_iter().map(MenuOption::Volume))
+            .chain([MenuOption::Back])
+            .collect();
+
+        Self {
+            menu_options,
+            menu_index: 0,
+        }
+    }
+
+    fn text_for_menu_option(
+        &self,
+        settings: &crate::settings::Settings,
+        menu_option: &MenuOption,
+    ) -> String {
+        match menu_option {
+            MenuOption::Mute => format!("Mute: {}", settings.is_muted()),
+            MenuOption::Volume(bus) => {
+                format!(
+                    "{}: {}%",
+                    bus.name(),
+                    (settings.volume(*bus) * 100.).round()
+                )
+            }
+            MenuOption::Back => "Back".to_string(),
+        }
+    }
+}
+
+impl Scene for AudioSettings {
+    fn update(&mut self, ctx: &mut Context) {
+        if action_pressed(Action::Cancel, ctx) {
+            ctx.pop_scene();
+            play_sfx(ctx, Sfx::MenuCancel);
+            return;
+        }
+
+        if action_repeated(Action::Up, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
+
+            if self.menu_index == 0 {
+                self.menu_index = self.menu_options.len() - 1;
+            } else {
+                self.menu_index -= 1;
+            }
+        }
+        if action_repeated(Action::Down, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
+
+            if self.menu_index == self.menu_options.len() - 1 {
+                self.menu_index = 0;
+            } else {
+                self.menu_index += 1;
+            }
+        }
+
+        let menu_option = self
+            .menu_options
+            .get(self.menu_index)
+            .expect("audio settings menu index out of bounds");
+
+        if let MenuOption::Volume(bus) = menu_option {
+            let steps = action_repeated(Action::Right, ctx) as i32
+                - action_repeated(Action::Left, ctx) as i32;
+            if steps != 0 {
+                ctx.settings.change_volume(*bus, steps);
+                // played after the change so it's heard at the new volume
+                play_sfx(ctx, Sfx::MenuMove);
+            }
+        }
+
+        if action_pressed(Action::Confirm, ctx) {
+            play_sfx(ctx, Sfx::MenuSelect);
+
+            match menu_option {
+                MenuOption::Mute => {
+                    ctx.settings.toggle_mute();
+                }
+                MenuOption::Volume(_) => {}
+                MenuOption::Back => {
+                    ctx.pop_scene();
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        draw_text(ctx, "Audio", X_INSET, 128., Size::Large, WHITE);
+
+        for (i, menu_option) in self.menu_options.iter().enumerate() {
+            let color = if self.menu_index == i { RED } else { WHITE };
+            let y = 200. + (i as f32 * 36.);
+
+            let text = self.text_for_menu_option(&ctx.settings, menu_option);
+            draw_text(ctx, text.as_str(), X_INSET, y, Size::Medium, color);
+
+            if let MenuOption::Volume(bus) = menu_option {
+                let slider_y = y - SLIDER_HEIGHT;
+                draw_rectangle(SLIDER_X, slider_y, SLIDER_WIDTH, SLIDER_HEIGHT, DARKGRAY);
+                draw_rectangle(
+                    SLIDER_X,
+                    slider_y,
+                    SLIDER_WIDTH * ctx.settings.volume(*bus),
+                    SLIDER_HEIGHT,
+                    color,
+                );
+                draw_rectangle_lines(SLIDER_X, slider_y, SLIDER_WIDTH, SLIDER_HEIGHT, 2., color);
+            }
+        }
+
+        draw_text(
+            ctx,
+            "Left and right turn the volume down and up",
+            X_INSET,
+            VIRTUAL_HEIGHT - 80.,
+            Size::Small,
+            BLUE,
+        );
+    }
+}
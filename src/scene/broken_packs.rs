@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use macroquad::color::{RED, WHITE};
+
+use super::Scene;
+use crate::audio::{play_sfx, Sfx};
+use crate::color::BLUE;
+use crate::consts::{TITLE_Y_INSET, VIRTUAL_HEIGHT, X_INSET};
+use crate::input::{action_pressed, action_repeated, Action, InputLayer};
+use crate::level::pack::BrokenPack;
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// how many broken packs fit on screen at once
+const VISIBLE_COUNT: usize = 7;
+const ROW_HEIGHT: f32 = 60.;
+
+/// scene listing the packs that couldn't be loaded and why
+pub struct BrokenPacks {
+    broken_packs: Vec<BrokenPack>,
+    /// index of the first broken pack shown, for scrolling
+    scroll_index: usize,
+}
+
+impl BrokenPacks {
+    pub fn new(_ctx: &Context, broken_packs: Vec<BrokenPack>) -> Self {
+        Self {
+            broken_packs,
+            scroll_index: 0,
+        }
+    }
+}
+
+impl Scene for BrokenPacks {
+    fn update(&mut self, ctx: &mut Context) {
+        if action_pressed(Action::Cancel, ctx) || action_pressed(Action::Confirm, ctx) {
+            play_sfx(ctx, Sfx::MenuCancel);
+            ctx.pop_scene();
+            return;
+        }
+
+        let max_scroll_index = self.broken_packs.len().saturating_sub(VISIBLE_COUNT);
+
+        if action_repeated(Action::Up, ctx) && self.scroll_index > 0 {
+            play_sfx(ctx, Sfx::MenuMove);
+            self.scroll_index -= 1;
+        }
+        if action_repeated(Action::Down, ctx) && self.scroll_index < max_scroll_index {
+            play_sfx(ctx, Sfx::MenuMove);
+            self.scroll_index += 1;
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        draw_text(
+            ctx,
+            "Broken Packs",
+            X_INSET,
+            TITLE_Y_INSET,
+            Size::Large,
+            WHITE,
+        );
+
+        for (i, broken_pack) in self
+            .broken_packs
+            .iter()
+            .skip(self.scroll_index)
+            .take(VISIBLE_COUNT)
+            .enumerate()
+        {
+            let y = 190. + i as f32 * ROW_HEIGHT;
+            let file_name = Path::new(&broken_pack.file)
+                .file_name()
+                .map_or(broken_pack.file.clone(), |f| {
+                    f.to_string_lossy().to_string()
+                });
+
+            draw_text(ctx, &file_name, X_INSET, y, Size::Medium, BLUE);
+            draw_text(
+                ctx,
+                &broken_pack.reason,
+                X_INSET,
+                y + 24.,
+                Size::Small,
+                WHITE,
+            );
+        }
+
+        let controls = ctx.settings.controls();
+        let hint = format!(
+            "Scroll = {} | Return = {} or {}",
+            controls.directions_hint(InputLayer::Menu, &[Action::Up, Action::Down]),
+            controls.hint(InputLayer::Menu, Action::Confirm),
+            controls.hint(InputLayer::Menu, Action::Cancel)
+        );
+        draw_text(
+            ctx,
+            hint.as_str(),
+            X_INSET,
+            VIRTUAL_HEIGHT - 40.,
+            Size::Small,
+            RED,
+        );
+    }
+}
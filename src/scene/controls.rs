@@ -0,0 +1,218 @@
+use macroquad::color::{RED, WHITE};
+use macroquad::input::KeyCode;
+
+use super::Scene;
+use crate::audio::{play_sfx, Sfx};
+use crate::color::BLUE;
+use crate::consts::{VIRTUAL_HEIGHT, X_INSET};
+use crate::input::{action_pressed, action_repeated, Action, Binding, InputLayer, InputMap};
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// stops waiting for a key or button without binding anything, so it's never bound from here
+const STOP_LISTENING_KEY: KeyCode = KeyCode::Escape;
+
+/// scene for changing which keys and gamepad buttons trigger each action, opened from settings
+pub struct Controls {
+    /// the layer whose bindings are shown, switched between with left and right
+    layer: InputLayer,
+    menu_index: usize,
+    /// the action waiting for a key or button to be pressed
+    listening: Option<Action>,
+    status: Option<String>,
+}
+
+enum MenuOption {
+    Action(Action),
+    ResetAll,
+    Back,
+}
+
+impl Controls {
+    pub fn new(_ctx: &Context) -> Self {
+        Self {
+            layer: InputLayer::Gameplay,
+            menu_index: 0,
+            listening: None,
+            status: None,
+        }
+    }
+
+    fn menu_options(&self) -> Vec<MenuOption> {
+        self.layer
+            .actions()
+            .iter()
+            .map(|action| MenuOption::Action(*action))
+            .chain([MenuOption::ResetAll, MenuOption::Back])
+            .collect()
+    }
+
+    fn text_for_menu_option(&self, controls: &InputMap, menu_option: &MenuOption) -> String {
+        match menu_option {
+            MenuOption::Action(action) if self.listening == Some(*action) => {
+                format!(
+                    "{}: press a key or button, or {} to stop",
+                    action.name(),
+                    Binding::Key(STOP_LISTENING_KEY).label()
+                )
+            }
+            MenuOption::Action(action) => {
+                let bindings: Vec<String> = controls
+                    .bindings(self.layer, *action)
+                    .iter()
+                    .map(|b| b.label())
+                    .collect();
+                format!("{}: {}", action.name(), bindings.join(", "))
+            }
+            MenuOption::ResetAll => "Reset to Defaults".to_string(),
+            MenuOption::Back => "Back".to_string(),
+        }
+    }
+
+    /// binds or unbinds the next key or button pressed
+    fn listen(&mut self, ctx: &mut Context, action: Action) {
+        let Some(binding) = ctx.input.binding_pressed() else {
+            return;
+        };
+        self.listening = None;
+        if binding == Binding::Key(STOP_LISTENING_KEY) {
+            play_sfx(ctx, Sfx::MenuCancel);
+            return;
+        }
+
+        let mut controls = ctx.settings.controls().clone();
+        match controls.toggle_binding(self.layer, action, binding) {
+            Ok(()) => {
+                ctx.settings.set_controls(controls);
+                self.status = None;
+                play_sfx(ctx, Sfx::MenuSelect);
+            }
+            Err(err) => {
+                self.status = Some(err);
+                play_sfx(ctx, Sfx::MenuCancel);
+            }
+        }
+    }
+}
+
+impl Scene for Controls {
+    fn update(&mut self, ctx: &mut Context) {
+        if let Some(action) = self.listening {
+            self.listen(ctx, action);
+            return;
+        }
+
+        if action_pressed(Action::Cancel, ctx) {
+            ctx.pop_scene();
+            play_sfx(ctx, Sfx::MenuCancel);
+            return;
+        }
+
+        if action_pressed(Action::Left, ctx) || action_pressed(Action::Right, ctx) {
+            let index = InputLayer::ALL
+                .iter()
+                .position(|layer| *layer == self.layer)
+                .unwrap_or(0);
+            let len = InputLayer::ALL.len();
+            let index = if action_pressed(Action::Left, ctx) {
+                (index + len - 1) % len
+            } else {
+                (index + 1) % len
+            };
+            self.layer = InputLayer::ALL[index];
+            self.menu_index = 0;
+            self.status = None;
+            play_sfx(ctx, Sfx::MenuMove);
+        }
+
+        let menu_options = self.menu_options();
+        if action_repeated(Action::Up, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
+
+            if self.menu_index == 0 {
+                self.menu_index = menu_options.len() - 1;
+            } else {
+                self.menu_index -= 1;
+            }
+        }
+        if action_repeated(Action::Down, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
+
+            if self.menu_index == menu_options.len() - 1 {
+                self.menu_index = 0;
+            } else {
+                self.menu_index += 1;
+            }
+        }
+
+        let menu_option = menu_options
+            .get(self.menu_index)
+            .expect("controls menu index out of bounds");
+
+        if action_pressed(Action::Reset, ctx) {
+            if let MenuOption::Action(action) = menu_option {
+                let mut controls = ctx.settings.controls().clone();
+                match controls.reset_action(self.layer, *action) {
+                    Ok(()) => {
+                        ctx.settings.set_controls(controls);
+                        self.status = Some(format!("Reset {} to its defaults", action.name()));
+                        play_sfx(ctx, Sfx::MenuSelect);
+                    }
+                    Err(err) => {
+                        self.status = Some(err);
+                        play_sfx(ctx, Sfx::MenuCancel);
+                    }
+                }
+            }
+        }
+
+        if action_pressed(Action::Confirm, ctx) {
+            play_sfx(ctx, Sfx::MenuSelect);
+
+            match menu_option {
+                MenuOption::Action(action) => {
+                    self.listening = Some(*action);
+                }
+                MenuOption::ResetAll => {
+                    ctx.settings.set_controls(InputMap::default());
+                    self.status = Some("Reset every action to its defaults".to_string());
+                }
+                MenuOption::Back => {
+                    ctx.pop_scene();
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        let title = format!("Controls: < {} >", self.layer.name());
+        draw_text(ctx, title.as_str(), X_INSET, 128., Size::Large, WHITE);
+
+        let controls = ctx.settings.controls().clone();
+        for (i, menu_option) in self.menu_options().iter().enumerate() {
+            let color = if self.menu_index == i { RED } else { WHITE };
+
+            let text = self.text_for_menu_option(&controls, menu_option);
+            draw_text(
+                ctx,
+                text.as_str(),
+                X_INSET,
+                180. + (i as f32 * 36.),
+                Size::Small,
+                color,
+            );
+        }
+
+        let status = self.status.clone().unwrap_or_else(|| {
+            "Left and right switch layers, Confirm adds or removes a key, Reset restores defaults".to_string()
+        });
+        draw_text(
+            ctx,
+            status.as_str(),
+            X_INSET,
+            VIRTUAL_HEIGHT - 80.,
+            Size::Small,
+            BLUE,
+        );
+    }
+}
@@ -1,32 +1,30 @@
 use macroquad::color::{RED, WHITE};
 
 use super::Scene;
-use crate::audio::play_sfx;
+use crate::audio::{play_sfx, Sfx};
 use crate::consts::TITLE_Y_INSET;
 use crate::consts::VIRTUAL_HEIGHT;
 use crate::consts::X_INSET;
 use crate::input::action_pressed;
 use crate::input::Action;
+use crate::input::InputLayer;
 use crate::text::Size;
 use crate::{context::Context, text::draw_text};
 
-/// sub-scene for displaying who worked on the game
-pub struct Credits {
-    pub active: bool,
-}
+/// scene for displaying who worked on the game
+pub struct Credits;
+
 impl Credits {
     pub fn new(_ctx: &Context) -> Self {
-        Self { active: false }
+        Self
     }
 }
 
 impl Scene for Credits {
     fn update(&mut self, ctx: &mut Context) {
-        if action_pressed(Action::Cancel, &ctx.gamepads)
-            || action_pressed(Action::Confirm, &ctx.gamepads)
-        {
-            play_sfx(ctx, &ctx.audio.sfx.menu_cancel);
-            self.active = false;
+        if action_pressed(Action::Cancel, ctx) || action_pressed(Action::Confirm, ctx) {
+            play_sfx(ctx, Sfx::MenuCancel);
+            ctx.pop_scene();
         }
     }
 
@@ -66,9 +64,15 @@ impl Scene for Credits {
             WHITE,
         );
 
+        let controls = ctx.settings.controls();
+        let hint = format!(
+            "Press {} or {} to return",
+            controls.hint(InputLayer::Menu, Action::Confirm),
+            controls.hint(InputLayer::Menu, Action::Cancel)
+        );
         draw_text(
             ctx,
-            "Press Z or X to return",
+            hint.as_str(),
             X_INSET,
             VIRTUAL_HEIGHT - 120.,
             Size::Medium,
@@ -1,76 +1,107 @@
-use macroquad::input::is_key_pressed;
-
 use super::pause::Pause;
-use super::{EScene, Scene};
-use crate::audio::play_sfx;
+use super::Scene;
+use crate::achievement::{self, Event};
+use crate::audio::{play_sfx, Sfx};
 use crate::context::Context;
+#[cfg(not(target_family = "wasm"))]
+use crate::fs::FileWatcher;
 use crate::input::action_pressed;
 use crate::input::Action;
+use crate::input::InputLayer;
 use crate::level::pack::PackLevel;
 use crate::level::{pack::Pack, playable_level::PlayableLevel};
+use crate::transition::TransitionKind;
 
 pub struct Gameplay {
     level: PlayableLevel,
     pack: Pack,
     level_index: usize,
-    pause_subscene: Pause,
+    /// the music playlist for the pack
+    playlist: String,
+    /// reloads the level when the pack file is saved, for designing levels
+    #[cfg(not(target_family = "wasm"))]
+    pack_watcher: Option<FileWatcher>,
 }
 
 impl Scene for Gameplay {
     fn update(&mut self, ctx: &mut Context) {
-        if self.pause_subscene.active {
-            self.pause_subscene.update(ctx);
-        } else {
-            if action_pressed(Action::Pause, &ctx.gamepads) {
-                self.pause_subscene.active = true;
-                play_sfx(ctx, &ctx.audio.sfx.menu_select);
-            }
+        if action_pressed(Action::Pause, ctx) {
+            let pause = Pause::new(ctx);
+            ctx.push_scene(pause);
+            play_sfx(ctx, Sfx::MenuSelect);
+        }
 
-            self.level.update(ctx);
-
-            if ctx.load_next_level {
-                ctx.load_next_level = false;
-                self.level_index += 1;
-                if self.level_index >= self.pack.levels.len() {
-                    ctx.switch_scene_to = Some(EScene::LevelSelect(self.pack.clone()));
-                } else {
-                    self.sync_to_ctx(ctx);
-                    self.level = PlayableLevel::new(
-                        self.pack.slug.clone(),
-                        self.pack.levels.get(self.level_index).unwrap(),
-                    );
-                }
-            }
+        let was_complete = self.level.complete;
+        self.level.update(ctx);
+        if self.level.complete && !was_complete {
+            self.level_completed(ctx);
+        }
+
+        if !self.level.complete {
+            ctx.save.record_play_time(
+                &self.pack.slug,
+                &self.level.level.title,
+                ctx.input.frame_time(),
+            );
+        }
 
-            // reloads the current level from the pack file
-            // unsure if this should be debug or not...
-            if is_key_pressed(macroquad::miniquad::KeyCode::Key9) {
-                play_sfx(ctx, &ctx.audio.sfx.reset);
-                ctx.reload_level = true;
+        if ctx.load_next_level {
+            ctx.load_next_level = false;
+            self.level_index += 1;
+            if self.level_index >= self.pack.levels.len() {
+                ctx.pop_scene();
+            } else {
+                let level = self.pack.levels[self.level_index].clone();
+                let next_level = Gameplay::new(ctx, level, self.level_index, self.pack.clone());
+                ctx.set_scene_transition(TransitionKind::Wipe);
+                ctx.replace_scene(next_level);
             }
         }
+
+        #[cfg(not(target_family = "wasm"))]
+        if self
+            .pack_watcher
+            .as_mut()
+            .is_some_and(|w| w.changed(ctx.input.frame_time()))
+        {
+            self.hot_reload(ctx);
+        }
+
+        // reloads the current level from the pack file
+        // unsure if this should be debug or not...
+        if action_pressed(Action::ReloadLevel, ctx) {
+            play_sfx(ctx, Sfx::Reset);
+            ctx.reload_level = true;
+        }
+    }
+
+    fn input_layer(&self) -> InputLayer {
+        InputLayer::Gameplay
+    }
+
+    fn music(&self) -> Option<&str> {
+        Some(&self.playlist)
     }
 
     fn draw(&mut self, ctx: &mut Context) {
-        if self.pause_subscene.active {
-            self.pause_subscene.draw(ctx);
-        } else {
-            self.level.draw(ctx);
-        }
+        self.level.draw(ctx);
     }
 }
 
 impl Gameplay {
-    pub async fn new(ctx: &mut Context, level: PackLevel, level_index: usize, pack: Pack) -> Self {
+    pub fn new(ctx: &mut Context, level: PackLevel, level_index: usize, pack: Pack) -> Self {
         let level = PlayableLevel::new(pack.slug.clone(), &level);
-        let pause_subscene = Pause::new(ctx, pack.clone());
         let mut gameplay = Self {
             level_index,
             level,
+            playlist: ctx.audio.music.playlist_for_pack(&pack.slug).to_string(),
+            #[cfg(not(target_family = "wasm"))]
+            pack_watcher: pack.file.as_ref().map(FileWatcher::new),
             pack,
-            pause_subscene,
         };
         gameplay.sync_to_ctx(ctx);
+        ctx.save
+            .record_attempt(&gameplay.pack.slug, &gameplay.level.level.title);
         gameplay
     }
 
@@ -80,4 +111,65 @@ impl Gameplay {
         ctx.current_pack_file = Some(self.pack.file.as_ref().unwrap().clone());
         ctx.current_level_index = Some(self.level_index);
     }
+
+    /// reloads the pack from disk after it changed, keeping the same level and replaying the
+    /// moves made so far if they can still be made
+    #[cfg(not(target_family = "wasm"))]
+    fn hot_reload(&mut self, ctx: &mut Context) {
+        let Some(pack_file) = self.pack.file.clone() else {
+            return;
+        };
+        let pack = match Pack::read(&pack_file) {
+            Ok(pack) => pack,
+            // keep playing the level as it was so the pack can be fixed and saved again
+            Err(err) => {
+                ctx.toasts.push(format!("Reload failed: {}", err));
+                return;
+            }
+        };
+        let Some(pack_level) = pack.levels.get(self.level_index) else {
+            ctx.toasts.push(format!(
+                "Reload failed: level {} isn't in the pack anymore",
+                self.level_index + 1
+            ));
+            return;
+        };
+
+        let moves = self.level.move_history();
+        let was_complete = self.level.complete;
+        let rewound = self.level.rewound;
+        self.level = PlayableLevel::new(pack.slug.clone(), pack_level);
+        self.level.rewound = rewound;
+        if self.level.replay(&moves) {
+            ctx.toasts.push("Level reloaded".to_string());
+        } else {
+            ctx.toasts
+                .push("Level reloaded, moves couldn't be replayed".to_string());
+        }
+        self.pack = pack;
+        play_sfx(ctx, Sfx::Reset);
+
+        // the changes can finish the level, like moving a storage location under a crate
+        if self.level.complete && !was_complete {
+            play_sfx(ctx, Sfx::LevelComplete);
+            self.level.save_completion(ctx);
+            self.level_completed(ctx);
+        }
+    }
+
+    /// checks achievements for the level just completed, after it was saved
+    fn level_completed(&self, ctx: &mut Context) {
+        achievement::handle_event(
+            ctx,
+            Event::LevelCompleted {
+                pack: &self.pack,
+                level_title: &self.level.level.title,
+                steps: self.level.steps,
+                pushes: self.level.pushes,
+                rewound: self.level.rewound,
+            },
+        );
+        // written right away since on web the tab can be closed without warning
+        ctx.save.persist();
+    }
 }
@@ -1,66 +1,69 @@
-use super::{EScene, Scene};
-use crate::audio::play_sfx;
-use crate::color::BLUE;
+use super::gameplay::Gameplay;
+use super::Scene;
+use crate::audio::music::MENU_PLAYLIST;
+use crate::audio::{play_sfx, Sfx};
+use crate::color::{BLUE, GRAY};
 use crate::consts::*;
 use crate::context::Context;
-use crate::input::{action_down, action_pressed, Action};
+use crate::input::{action_pressed, action_repeated, Action, InputLayer};
 use crate::level::pack::Pack;
 use crate::text::{self, draw_text};
 use macroquad::color::{RED, WHITE};
-use macroquad::time::get_frame_time;
 
 pub struct LevelSelect {
     pack: Pack,
     focused_level_index: i32,
-    move_held_delay: f32,
 }
 
 impl Scene for LevelSelect {
     fn update(&mut self, ctx: &mut Context) {
-        if self.move_held_delay > 0.0 {
-            self.move_held_delay -= get_frame_time();
-        }
-
-        if action_pressed(Action::Left, &ctx.gamepads)
-            || (action_down(Action::Left, &ctx.gamepads) && self.move_held_delay <= 0.)
-        {
-            self.move_held_delay = MOVE_HELD_DELAY;
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
+        if action_repeated(Action::Left, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
             self.focused_level_index -= 1;
             if self.focused_level_index < 0 {
                 self.focused_level_index = (self.pack.levels.len() - 1) as i32;
             }
         }
-        if action_pressed(Action::Right, &ctx.gamepads)
-            || (action_down(Action::Right, &ctx.gamepads) && self.move_held_delay <= 0.)
-        {
-            self.move_held_delay = MOVE_HELD_DELAY;
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
+        if action_repeated(Action::Right, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
             self.focused_level_index += 1;
             if self.focused_level_index > (self.pack.levels.len() - 1) as i32 {
                 self.focused_level_index = 0;
             }
         }
 
-        if action_pressed(Action::Confirm, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_select);
+        if action_pressed(Action::Confirm, ctx) {
+            if !self
+                .pack
+                .is_level_unlocked(&ctx.save, self.focused_level_index as usize)
+            {
+                play_sfx(ctx, Sfx::MenuCancel);
+                return;
+            }
+            play_sfx(ctx, Sfx::MenuSelect);
             let level = self
                 .pack
                 .levels
                 .get(self.focused_level_index as usize)
                 .expect("level index not present in loaded pack");
-            ctx.switch_scene_to = Some(EScene::Gameplay(
+            let gameplay = Gameplay::new(
+                ctx,
                 level.to_owned(),
                 self.focused_level_index as usize,
                 self.pack.to_owned(),
-            ));
+            );
+            ctx.push_scene(gameplay);
         }
 
-        if action_pressed(Action::Cancel, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_cancel);
-            ctx.switch_scene_to = Some(EScene::MainMenu);
+        if action_pressed(Action::Cancel, ctx) {
+            play_sfx(ctx, Sfx::MenuCancel);
+            ctx.pop_scene();
         }
     }
+    fn music(&self) -> Option<&str> {
+        Some(MENU_PLAYLIST)
+    }
+
     fn draw(&mut self, ctx: &mut Context) {
         draw_text(
             ctx,
@@ -79,12 +82,16 @@ impl Scene for LevelSelect {
             WHITE,
         );
 
+        let completed = self.pack.completed_levels(&ctx.save);
         for (i, level) in &mut self.pack.levels.iter().enumerate() {
             let title = level.title.clone();
-            let is_level_complete = ctx.save.is_level_complete(&self.pack.slug, &title);
+            let is_level_complete = completed[i];
+            let is_level_unlocked = self.pack.progression.is_level_unlocked(&completed, i);
 
             let color = if self.focused_level_index == i as i32 {
                 RED
+            } else if !is_level_unlocked {
+                GRAY
             } else if is_level_complete {
                 BLUE
             } else {
@@ -103,9 +110,22 @@ impl Scene for LevelSelect {
             );
 
             if is_level_complete {
+                let label = match ctx.save.level_medal(&self.pack.slug, &title) {
+                    Some(medal) => format!("complete - {}", medal.to_string().to_lowercase()),
+                    None => "complete".to_string(),
+                };
                 draw_text(
                     ctx,
-                    "complete",
+                    label.as_str(),
+                    title_x,
+                    title_y + 40.,
+                    text::Size::Small,
+                    color,
+                );
+            } else if !is_level_unlocked {
+                draw_text(
+                    ctx,
+                    "locked",
                     title_x,
                     title_y + 40.,
                     text::Size::Small,
@@ -114,9 +134,15 @@ impl Scene for LevelSelect {
             }
         }
 
+        let hint = format!(
+            "Press {} to select level",
+            ctx.settings
+                .controls()
+                .hint(InputLayer::Menu, Action::Confirm)
+        );
         draw_text(
             ctx,
-            "Press Z to select level",
+            hint.as_str(),
             X_INSET,
             VIRTUAL_HEIGHT - 120.,
             text::Size::Medium,
@@ -126,7 +152,7 @@ impl Scene for LevelSelect {
 }
 
 impl LevelSelect {
-    pub async fn new(ctx: &mut Context, pack: Pack) -> Self {
+    pub fn new(ctx: &Context, pack: Pack) -> Self {
         let mut focused_level_index: i32 = 0;
 
         for (i, level) in pack.levels.iter().enumerate() {
@@ -139,7 +165,6 @@ impl LevelSelect {
         Self {
             pack,
             focused_level_index,
-            move_held_delay: 0.,
         }
     }
 }
@@ -1,29 +1,38 @@
+use super::achievements::Achievements;
+use super::broken_packs::BrokenPacks;
 use super::credits::Credits;
+use super::level_select::LevelSelect;
+use super::profiles::Profiles;
 use super::settings::Settings;
-use super::{EScene, Scene};
-use crate::assets_path::determine_asset_path;
-use crate::audio::play_sfx;
+use super::stats::Stats;
+use super::Scene;
+use crate::audio::music::MENU_PLAYLIST;
+use crate::audio::{play_sfx, Sfx};
+use crate::color::GRAY;
 use crate::consts::*;
 use crate::context::Context;
-use crate::input::{action_down, action_pressed, Action};
+use crate::input::{action_pressed, action_repeated, Action, InputLayer};
 use crate::level::pack::Pack;
 use crate::text::{self, draw_text};
 use macroquad::color::{RED, WHITE};
-use macroquad::time::get_frame_time;
+
+/// vertical space between each menu option
+const MENU_OPTION_SPACING: f32 = 36.;
 
 pub struct MainMenu {
     packs: Vec<Pack>,
     focused_pack_index: i32,
     menu_options: Vec<MenuOption>,
     menu_index: usize,
-    settings_subscene: Settings,
-    credits_subscene: Credits,
-    move_held_delay: f32,
-    packs_complete_count: Vec<i32>,
 }
 
 enum MenuOption {
     PackSelect,
+    /// only shown when some packs couldn't be loaded
+    BrokenPacks,
+    Profile,
+    Stats,
+    Achievements,
     Settings,
     Credits,
     #[cfg(not(target_family = "wasm"))]
@@ -31,109 +40,74 @@ enum MenuOption {
 }
 
 impl MainMenu {
-    pub async fn new(ctx: &mut Context) -> Self {
-        let base_assets_path = determine_asset_path();
+    pub fn new(ctx: &Context) -> Self {
+        let mut main_menu = Self {
+            packs: vec![],
+            focused_pack_index: 0,
+            menu_options: vec![],
+            menu_index: 0,
+        };
+        main_menu.refresh(ctx);
+        main_menu
+    }
+
+    /// updates the packs and menu options to match the packs on the Context
+    fn refresh(&mut self, ctx: &Context) {
+        self.packs = ctx.packs.packs.clone();
+        if self.focused_pack_index as usize >= self.packs.len() {
+            self.focused_pack_index = 0;
+        }
 
-        let menu_options = vec![
-            MenuOption::PackSelect,
+        self.menu_options = vec![MenuOption::PackSelect];
+        if !ctx.packs.broken.is_empty() {
+            self.menu_options.push(MenuOption::BrokenPacks);
+        }
+        self.menu_options.extend([
+            MenuOption::Profile,
+            MenuOption::Stats,
+            MenuOption::Achievements,
             MenuOption::Settings,
             MenuOption::Credits,
             #[cfg(not(target_family = "wasm"))]
             MenuOption::Quit,
-        ];
-
-        let packs = vec![
-            Pack::load(
-                ctx,
-                base_assets_path.join("packs/pack-a.toml").to_str().unwrap(),
-            )
-            .await,
-            Pack::load(
-                ctx,
-                base_assets_path
-                    .join("packs/yoshio-murase-automatic.toml")
-                    .to_str()
-                    .unwrap(),
-            )
-            .await,
-        ];
-
-        let mut packs_complete_count = vec![];
-        for pack in &packs {
-            packs_complete_count.push(
-                pack.levels
-                    .iter()
-                    .map(|l| {
-                        if ctx.save.is_level_complete(&pack.slug, &l.title.clone()) {
-                            1
-                        } else {
-                            0
-                        }
-                    })
-                    .sum(),
-            )
-        }
-
-        Self {
-            menu_options,
-            menu_index: 0,
-            packs,
-            focused_pack_index: 0,
-            settings_subscene: Settings::new(ctx, false),
-            credits_subscene: Credits::new(ctx),
-            packs_complete_count,
-            move_held_delay: 0.,
+        ]);
+        if self.menu_index >= self.menu_options.len() {
+            self.menu_index = 0;
         }
     }
 
-    fn text_for_menu_option(&self, menu_option: &MenuOption) -> &str {
+    fn text_for_menu_option(&self, ctx: &Context, menu_option: &MenuOption) -> String {
         match menu_option {
-            MenuOption::PackSelect => "",
-            MenuOption::Settings => "Settings",
-            MenuOption::Credits => "Credits",
+            MenuOption::PackSelect => "".to_string(),
+            MenuOption::BrokenPacks => format!("Broken Packs ({})", ctx.packs.broken.len()),
+            MenuOption::Profile => format!("Profile: {}", ctx.profiles.active().name),
+            MenuOption::Stats => "Stats".to_string(),
+            MenuOption::Achievements => "Achievements".to_string(),
+            MenuOption::Settings => "Settings".to_string(),
+            MenuOption::Credits => "Credits".to_string(),
             #[cfg(not(target_family = "wasm"))]
-            MenuOption::Quit => "Quit",
+            MenuOption::Quit => "Quit".to_string(),
         }
     }
 }
 
 impl Scene for MainMenu {
     fn update(&mut self, ctx: &mut Context) {
-        if self.settings_subscene.active {
-            self.settings_subscene.update(ctx);
-            return;
-        }
-
-        if self.credits_subscene.active {
-            self.credits_subscene.update(ctx);
-            return;
-        }
-
-        if self.move_held_delay > 0.0 {
-            self.move_held_delay -= get_frame_time();
-        }
-
         let menu_option = self
             .menu_options
             .get(self.menu_index)
             .expect("pause menu index out of bounds");
 
-        if matches!(menu_option, MenuOption::PackSelect) {
-            if action_pressed(Action::Left, &ctx.gamepads)
-                || (action_down(Action::Left, &ctx.gamepads) && self.move_held_delay <= 0.)
-            {
-                self.move_held_delay = MOVE_HELD_DELAY;
-                play_sfx(ctx, &ctx.audio.sfx.menu_move);
+        if matches!(menu_option, MenuOption::PackSelect) && !self.packs.is_empty() {
+            if action_repeated(Action::Left, ctx) {
+                play_sfx(ctx, Sfx::MenuMove);
                 self.focused_pack_index -= 1;
                 if self.focused_pack_index < 0 {
                     self.focused_pack_index = (self.packs.len() - 1) as i32;
                 }
             }
-            if action_pressed(Action::Right, &ctx.gamepads)
-                || (action_down(Action::Right, &ctx.gamepads) && self.move_held_delay <= 0.)
-            {
-                self.move_held_delay = MOVE_HELD_DELAY;
-                play_sfx(ctx, &ctx.audio.sfx.menu_move);
+            if action_repeated(Action::Right, ctx) {
+                play_sfx(ctx, Sfx::MenuMove);
                 self.focused_pack_index += 1;
 
                 if self.focused_pack_index > (self.packs.len() - 1) as i32 {
@@ -142,22 +116,46 @@ impl Scene for MainMenu {
             }
         }
 
-        if action_pressed(Action::Confirm, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_select);
+        if action_pressed(Action::Confirm, ctx) {
+            let focused_pack = self.packs.get(self.focused_pack_index as usize);
+            if matches!(menu_option, MenuOption::PackSelect)
+                && focused_pack.is_some_and(|p| !p.is_unlocked(&ctx.save))
+            {
+                play_sfx(ctx, Sfx::MenuCancel);
+                return;
+            }
+            play_sfx(ctx, Sfx::MenuSelect);
 
             match menu_option {
                 MenuOption::PackSelect => {
-                    let pack = self
-                        .packs
-                        .get(self.focused_pack_index as usize)
-                        .expect("pack index not present in loaded packs");
-                    ctx.switch_scene_to = Some(EScene::LevelSelect(pack.to_owned()));
+                    if let Some(pack) = self.packs.get(self.focused_pack_index as usize) {
+                        let level_select = LevelSelect::new(ctx, pack.to_owned());
+                        ctx.push_scene(level_select);
+                    }
+                }
+                MenuOption::BrokenPacks => {
+                    let broken_packs = BrokenPacks::new(ctx, ctx.packs.broken.clone());
+                    ctx.push_scene(broken_packs);
+                }
+                MenuOption::Profile => {
+                    let profiles = Profiles::new(ctx);
+                    ctx.push_scene(profiles);
+                }
+                MenuOption::Stats => {
+                    let stats = Stats::new(ctx, &self.packs);
+                    ctx.push_scene(stats);
+                }
+                MenuOption::Achievements => {
+                    let achievements = Achievements::new(ctx);
+                    ctx.push_scene(achievements);
                 }
                 MenuOption::Settings => {
-                    self.settings_subscene.active = true;
+                    let settings = Settings::new(ctx);
+                    ctx.push_scene(settings);
                 }
                 MenuOption::Credits => {
-                    self.credits_subscene.active = true;
+                    let credits = Credits::new(ctx);
+                    ctx.push_scene(credits);
                 }
                 #[cfg(not(target_family = "wasm"))]
                 MenuOption::Quit => {
@@ -166,11 +164,8 @@ impl Scene for MainMenu {
             }
         }
 
-        if action_pressed(Action::Up, &ctx.gamepads)
-            || (action_down(Action::Up, &ctx.gamepads) && self.move_held_delay <= 0.)
-        {
-            self.move_held_delay = MOVE_HELD_DELAY;
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
+        if action_repeated(Action::Up, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
 
             if self.menu_index == 0 {
                 self.menu_index = self.menu_options.len() - 1;
@@ -178,11 +173,8 @@ impl Scene for MainMenu {
                 self.menu_index -= 1;
             }
         }
-        if action_pressed(Action::Down, &ctx.gamepads)
-            || (action_down(Action::Down, &ctx.gamepads) && self.move_held_delay <= 0.)
-        {
-            self.move_held_delay = MOVE_HELD_DELAY;
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
+        if action_repeated(Action::Down, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
 
             if self.menu_index == self.menu_options.len() - 1 {
                 self.menu_index = 0;
@@ -191,17 +183,11 @@ impl Scene for MainMenu {
             }
         }
     }
-    fn draw(&mut self, ctx: &mut Context) {
-        if self.settings_subscene.active {
-            self.settings_subscene.draw(ctx);
-            return;
-        }
-
-        if self.credits_subscene.active {
-            self.credits_subscene.draw(ctx);
-            return;
-        }
+    fn music(&self) -> Option<&str> {
+        Some(MENU_PLAYLIST)
+    }
 
+    fn draw(&mut self, ctx: &mut Context) {
         let menu_option = self
             .menu_options
             .get(self.menu_index)
@@ -216,11 +202,29 @@ impl Scene for MainMenu {
             WHITE,
         );
 
+        if self.packs.is_empty() {
+            draw_text(
+                ctx,
+                "No packs found",
+                X_INSET,
+                VIRTUAL_HEIGHT / 2. - 58.,
+                text::Size::Medium,
+                if matches!(menu_option, MenuOption::PackSelect) {
+                    RED
+                } else {
+                    WHITE
+                },
+            );
+        }
+
         for (i, pack) in &mut self.packs.iter().enumerate() {
+            let unlock_text = pack.unlock_text(&ctx.save, &self.packs);
             let color = if (self.focused_pack_index == i as i32)
                 && matches!(menu_option, MenuOption::PackSelect)
             {
                 RED
+            } else if unlock_text.is_some() {
+                GRAY
             } else {
                 WHITE
             };
@@ -244,14 +248,17 @@ impl Scene for MainMenu {
                 text::Size::Small,
                 color,
             );
+            let progress_text = unlock_text.unwrap_or_else(|| {
+                let complete_count = pack
+                    .completed_levels(&ctx.save)
+                    .iter()
+                    .filter(|c| **c)
+                    .count();
+                format!("{} levels ({} complete)", pack.levels.len(), complete_count)
+            });
             draw_text(
                 ctx,
-                format!(
-                    "{} levels ({} complete)",
-                    pack.levels.len(),
-                    self.packs_complete_count.get(i).unwrap(),
-                )
-                .as_str(),
+                progress_text.as_str(),
                 title_x,
                 title_y + 48.,
                 text::Size::Small,
@@ -262,19 +269,26 @@ impl Scene for MainMenu {
         for (i, menu_option) in self.menu_options.iter().enumerate() {
             let color = if self.menu_index == i { RED } else { WHITE };
 
+            let text = self.text_for_menu_option(ctx, menu_option);
             draw_text(
                 ctx,
-                self.text_for_menu_option(menu_option),
+                text.as_str(),
                 X_INSET,
-                400. + (i as f32 * 40.),
+                400. + (i as f32 * MENU_OPTION_SPACING),
                 text::Size::Medium,
                 color,
             );
         }
 
+        let controls = ctx.settings.controls();
+        let hint = format!(
+            "Change Select = {} | Confirm = {}",
+            controls.directions_hint(InputLayer::Menu, &[Action::Up, Action::Down]),
+            controls.hint(InputLayer::Menu, Action::Confirm)
+        );
         draw_text(
             ctx,
-            "Change Select = Arrow Keys | Confirm = Z",
+            hint.as_str(),
             X_INSET,
             VIRTUAL_HEIGHT - 40.,
             text::Size::Small,
@@ -290,4 +304,10 @@ impl Scene for MainMenu {
             WHITE,
         );
     }
+
+    /// picks up packs that were added or changed while playing, and progress made in them
+    fn resume(&mut self, ctx: &mut Context) {
+        ctx.reload_packs();
+        self.refresh(ctx);
+    }
 }
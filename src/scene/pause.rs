@@ -1,26 +1,22 @@
 use macroquad::color::{RED, WHITE};
-use macroquad::time::get_frame_time;
+use macroquad::shapes::draw_rectangle;
 
+use super::main_menu::MainMenu;
 use super::settings::Settings;
-use super::EScene;
 use super::Scene;
-use crate::audio::play_sfx;
+use crate::audio::{play_sfx, Sfx};
+use crate::color::OVERLAY;
 use crate::consts::X_INSET;
-use crate::consts::{MOVE_HELD_DELAY, TITLE_Y_INSET};
+use crate::consts::{TITLE_Y_INSET, VIRTUAL_HEIGHT, VIRTUAL_WIDTH};
 use crate::input::Action;
-use crate::input::{action_down, action_pressed};
-use crate::level::pack::Pack;
+use crate::input::{action_pressed, action_repeated};
 use crate::text::Size;
 use crate::{context::Context, text::draw_text};
 
-/// sub-scene rendered during gameplay with various options
+/// scene shown over the level during gameplay with various options
 pub struct Pause {
-    pub active: bool,
     menu_options: Vec<MenuOption>,
     menu_index: usize,
-    settings_subscene: Settings,
-    pack: Pack,
-    move_held_delay: f32,
 }
 
 enum MenuOption {
@@ -33,7 +29,7 @@ enum MenuOption {
 }
 
 impl Pause {
-    pub fn new(ctx: &mut Context, pack: Pack) -> Self {
+    pub fn new(_ctx: &Context) -> Self {
         let menu_options = vec![
             MenuOption::Resume,
             MenuOption::Settings,
@@ -46,10 +42,6 @@ impl Pause {
         Self {
             menu_options,
             menu_index: 0,
-            active: false,
-            move_held_delay: 0.,
-            settings_subscene: Settings::new(ctx, false),
-            pack,
         }
     }
 
@@ -67,28 +59,14 @@ impl Pause {
 
 impl Scene for Pause {
     fn update(&mut self, ctx: &mut Context) {
-        if self.settings_subscene.active {
-            self.settings_subscene.update(ctx);
+        if action_pressed(Action::Pause, ctx) || action_pressed(Action::Cancel, ctx) {
+            ctx.pop_scene();
+            play_sfx(ctx, Sfx::MenuCancel);
             return;
         }
 
-        if self.move_held_delay > 0.0 {
-            self.move_held_delay -= get_frame_time();
-        }
-
-        if action_pressed(Action::Pause, &ctx.gamepads)
-            || action_pressed(Action::Cancel, &ctx.gamepads)
-        {
-            self.active = false;
-            play_sfx(ctx, &ctx.audio.sfx.menu_cancel);
-            return;
-        }
-
-        if action_pressed(Action::Up, &ctx.gamepads)
-            || (action_down(Action::Up, &ctx.gamepads) && self.move_held_delay <= 0.)
-        {
-            self.move_held_delay = MOVE_HELD_DELAY;
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
+        if action_repeated(Action::Up, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
 
             if self.menu_index == 0 {
                 self.menu_index = self.menu_options.len() - 1;
@@ -96,11 +74,8 @@ impl Scene for Pause {
                 self.menu_index -= 1;
             }
         }
-        if action_pressed(Action::Down, &ctx.gamepads)
-            || (action_down(Action::Down, &ctx.gamepads) && self.move_held_delay <= 0.)
-        {
-            self.move_held_delay = MOVE_HELD_DELAY;
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
+        if action_repeated(Action::Down, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
 
             if self.menu_index == self.menu_options.len() - 1 {
                 self.menu_index = 0;
@@ -109,8 +84,8 @@ impl Scene for Pause {
             }
         }
 
-        if action_pressed(Action::Confirm, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_select);
+        if action_pressed(Action::Confirm, ctx) {
+            play_sfx(ctx, Sfx::MenuSelect);
 
             let menu_option = self
                 .menu_options
@@ -118,16 +93,20 @@ impl Scene for Pause {
                 .expect("pause menu index out of bounds");
             match menu_option {
                 MenuOption::Resume => {
-                    self.active = false;
+                    ctx.pop_scene();
                 }
                 MenuOption::Settings => {
-                    self.settings_subscene.active = true;
+                    let settings = Settings::new(ctx);
+                    ctx.push_scene(settings);
                 }
                 MenuOption::LevelSelect => {
-                    ctx.switch_scene_to = Some(EScene::LevelSelect(self.pack.clone()));
+                    // closes the level too, which is below the pause menu
+                    ctx.pop_scene();
+                    ctx.pop_scene();
                 }
                 MenuOption::MainMenu => {
-                    ctx.switch_scene_to = Some(EScene::MainMenu);
+                    let main_menu = MainMenu::new(ctx);
+                    ctx.reset_scenes(main_menu);
                 }
                 #[cfg(not(target_family = "wasm"))]
                 MenuOption::Quit => {
@@ -138,10 +117,8 @@ impl Scene for Pause {
     }
 
     fn draw(&mut self, ctx: &mut Context) {
-        if self.settings_subscene.active {
-            self.settings_subscene.draw(ctx);
-            return;
-        }
+        // dims the level so the menu can be read over it
+        draw_rectangle(0., 0., VIRTUAL_WIDTH, VIRTUAL_HEIGHT, OVERLAY);
 
         draw_text(ctx, "Pause", X_INSET, TITLE_Y_INSET, Size::Large, WHITE);
 
@@ -158,4 +135,29 @@ impl Scene for Pause {
             );
         }
     }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputMap;
+
+    #[test]
+    fn test_pause_closes_the_pause_menu() {
+        let pause = Pause {
+            menu_options: vec![],
+            menu_index: 0,
+        };
+        let layer = pause
+            .input_layer()
+            .layer_for(Action::Pause)
+            .expect("pause isn't checked in the pause menu");
+        assert!(!InputMap::default()
+            .bindings(layer, Action::Pause)
+            .is_empty());
+    }
 }
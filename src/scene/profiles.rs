@@ -0,0 +1,274 @@
+use macroquad::color::{RED, WHITE};
+use macroquad::input::KeyCode;
+
+use super::Scene;
+use crate::audio::{play_sfx, Sfx};
+use crate::color::BLUE;
+use crate::consts::{TITLE_Y_INSET, VIRTUAL_HEIGHT, X_INSET};
+use crate::input::{action_pressed, action_repeated, Action, Binding};
+use crate::profile::MAX_PROFILE_NAME_LEN;
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// scene for picking, creating, renaming, and deleting player profiles
+pub struct Profiles {
+    mode: Mode,
+    menu_index: usize,
+}
+
+enum Mode {
+    /// list of all profiles
+    List,
+    /// what to do with the profile that was picked from the list
+    Options(String),
+    /// making sure the player really wants to delete the profile
+    ConfirmDelete(String),
+    /// typing in a name, for the profile being renamed or a new one when `None`
+    Naming(Option<String>, String),
+}
+
+enum MenuOption {
+    Profile(String),
+    NewProfile,
+    PlayAs,
+    Rename,
+    Delete,
+    ConfirmDelete,
+    Back,
+}
+
+impl Profiles {
+    /// opens with the profile list, focused on the active profile
+    pub fn new(ctx: &Context) -> Self {
+        let mut profiles = Self {
+            mode: Mode::List,
+            menu_index: 0,
+        };
+        profiles.switch_mode(Mode::List);
+        profiles.menu_index = ctx
+            .profiles
+            .all()
+            .iter()
+            .position(|p| p.id == ctx.profiles.active().id)
+            .unwrap_or(0);
+        profiles
+    }
+
+    fn switch_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.menu_index = 0;
+    }
+
+    fn menu_options(&self, ctx: &Context) -> Vec<MenuOption> {
+        match &self.mode {
+            Mode::List => {
+                let mut options: Vec<MenuOption> = ctx
+                    .profiles
+                    .all()
+                    .iter()
+                    .map(|p| MenuOption::Profile(p.id.clone()))
+                    .collect();
+                options.push(MenuOption::NewProfile);
+                options.push(MenuOption::Back);
+                options
+            }
+            Mode::Options(_) => {
+                let mut options = vec![MenuOption::PlayAs, MenuOption::Rename];
+                if ctx.profiles.all().len() > 1 {
+                    options.push(MenuOption::Delete);
+                }
+                options.push(MenuOption::Back);
+                options
+            }
+            Mode::ConfirmDelete(_) => vec![MenuOption::Back, MenuOption::ConfirmDelete],
+            Mode::Naming(_, _) => vec![],
+        }
+    }
+
+    fn text_for_menu_option(&self, ctx: &Context, menu_option: &MenuOption) -> String {
+        match menu_option {
+            MenuOption::Profile(id) => profile_name(ctx, id),
+            MenuOption::NewProfile => "New Profile".to_string(),
+            MenuOption::PlayAs => "Play as This Profile".to_string(),
+            MenuOption::Rename => "Rename".to_string(),
+            MenuOption::Delete => "Delete".to_string(),
+            MenuOption::ConfirmDelete => "Yes, delete it and its progress".to_string(),
+            MenuOption::Back => match self.mode {
+                Mode::ConfirmDelete(_) => "No, keep it".to_string(),
+                _ => "Back".to_string(),
+            },
+        }
+    }
+
+    fn update_naming(&mut self, ctx: &mut Context, profile_id: Option<String>, name: String) {
+        let mut name = name;
+        for c in ctx.input.typed().chars() {
+            if !c.is_control() && name.chars().count() < MAX_PROFILE_NAME_LEN {
+                name.push(c);
+            }
+        }
+        let pressed = ctx.input.binding_pressed();
+        if pressed == Some(Binding::Key(KeyCode::Backspace)) {
+            name.pop();
+        }
+
+        if pressed == Some(Binding::Key(KeyCode::Escape)) {
+            play_sfx(ctx, Sfx::MenuCancel);
+            self.switch_mode(Mode::List);
+            return;
+        }
+
+        if pressed == Some(Binding::Key(KeyCode::Enter))
+            || pressed == Some(Binding::Key(KeyCode::KpEnter))
+        {
+            play_sfx(ctx, Sfx::MenuSelect);
+            match &profile_id {
+                Some(id) => ctx.profiles.rename(id, &name),
+                None => {
+                    ctx.profiles.create(&name);
+                }
+            }
+            self.switch_mode(Mode::List);
+            return;
+        }
+
+        self.mode = Mode::Naming(profile_id, name);
+    }
+}
+
+fn profile_name(ctx: &Context, profile_id: &str) -> String {
+    ctx.profiles
+        .get(profile_id)
+        .map(|p| p.name.clone())
+        .unwrap_or_default()
+}
+
+impl Scene for Profiles {
+    fn update(&mut self, ctx: &mut Context) {
+        if let Mode::Naming(profile_id, name) = &self.mode {
+            self.update_naming(ctx, profile_id.clone(), name.clone());
+            return;
+        }
+
+        if action_pressed(Action::Cancel, ctx) {
+            play_sfx(ctx, Sfx::MenuCancel);
+            match self.mode {
+                Mode::List => ctx.pop_scene(),
+                _ => self.switch_mode(Mode::List),
+            }
+            return;
+        }
+
+        let menu_options = self.menu_options(ctx);
+
+        if action_repeated(Action::Up, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
+
+            if self.menu_index == 0 {
+                self.menu_index = menu_options.len() - 1;
+            } else {
+                self.menu_index -= 1;
+            }
+        }
+        if action_repeated(Action::Down, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
+
+            if self.menu_index == menu_options.len() - 1 {
+                self.menu_index = 0;
+            } else {
+                self.menu_index += 1;
+            }
+        }
+
+        if action_pressed(Action::Confirm, ctx) {
+            play_sfx(ctx, Sfx::MenuSelect);
+
+            let menu_option = menu_options
+                .get(self.menu_index)
+                .expect("profiles menu index out of bounds");
+            let selected_profile_id = match &self.mode {
+                Mode::Options(id) | Mode::ConfirmDelete(id) => id.clone(),
+                _ => String::new(),
+            };
+            match menu_option {
+                MenuOption::Profile(id) => {
+                    self.switch_mode(Mode::Options(id.clone()));
+                }
+                MenuOption::NewProfile => {
+                    let name = ctx.profiles.next_default_name();
+                    self.switch_mode(Mode::Naming(None, name));
+                }
+                MenuOption::PlayAs => {
+                    ctx.switch_profile(&selected_profile_id);
+                    ctx.pop_scene();
+                }
+                MenuOption::Rename => {
+                    let name = profile_name(ctx, &selected_profile_id);
+                    self.switch_mode(Mode::Naming(Some(selected_profile_id), name));
+                }
+                MenuOption::Delete => {
+                    self.switch_mode(Mode::ConfirmDelete(selected_profile_id));
+                }
+                MenuOption::ConfirmDelete => {
+                    ctx.delete_profile(&selected_profile_id);
+                    self.switch_mode(Mode::List);
+                }
+                MenuOption::Back => match self.mode {
+                    Mode::List => ctx.pop_scene(),
+                    _ => self.switch_mode(Mode::List),
+                },
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        let title = match &self.mode {
+            Mode::List => "Profiles".to_string(),
+            Mode::Options(id) => profile_name(ctx, id),
+            Mode::ConfirmDelete(id) => format!("Delete {}?", profile_name(ctx, id)),
+            Mode::Naming(Some(_), _) => "Rename Profile".to_string(),
+            Mode::Naming(None, _) => "New Profile".to_string(),
+        };
+        draw_text(ctx, &title, X_INSET, TITLE_Y_INSET, Size::Large, WHITE);
+
+        if let Mode::Naming(_, name) = &self.mode {
+            let name = format!("{}_", name);
+            draw_text(ctx, &name, X_INSET, 240., Size::Medium, RED);
+            draw_text(
+                ctx,
+                "Type a name | Confirm = Enter | Cancel = Esc",
+                X_INSET,
+                VIRTUAL_HEIGHT - 120.,
+                Size::Small,
+                WHITE,
+            );
+            return;
+        }
+
+        let active_profile_id = ctx.profiles.active().id.clone();
+        for (i, menu_option) in self.menu_options(ctx).iter().enumerate() {
+            let is_active_profile =
+                matches!(menu_option, MenuOption::Profile(id) if *id == active_profile_id);
+            let color = if self.menu_index == i {
+                RED
+            } else if is_active_profile {
+                BLUE
+            } else {
+                WHITE
+            };
+
+            let mut text = self.text_for_menu_option(ctx, menu_option);
+            if is_active_profile {
+                text.push_str(" (playing)");
+            }
+            draw_text(
+                ctx,
+                text.as_str(),
+                X_INSET,
+                200. + (i as f32 * 40.),
+                Size::Medium,
+                color,
+            );
+        }
+    }
+}
@@ -1,43 +1,103 @@
 use macroquad::color::{RED, WHITE};
-use macroquad::time::get_frame_time;
+use macroquad::miniquad::window::{clipboard_get, clipboard_set};
 
+use super::audio_settings::AudioSettings;
+use super::controls::Controls;
 use super::Scene;
-use crate::audio::play_sfx;
-use crate::consts::{MOVE_HELD_DELAY, X_INSET};
+use crate::audio::{play_sfx, Sfx};
+use crate::color::BLUE;
+use crate::consts::{VIRTUAL_HEIGHT, X_INSET};
 use crate::input::Action;
-use crate::input::{action_down, action_pressed};
+use crate::input::{action_pressed, action_repeated};
+use crate::save::{Merged, Save};
 use crate::text::Size;
 use crate::{context::Context, text::draw_text};
 
-/// sub-scene for setting user preferences, at times a subscene
+/// scene for setting user preferences, opened from the main menu and the pause menu
 pub struct Settings {
-    pub active: bool,
     menu_options: Vec<MenuOption>,
     menu_index: usize,
-    move_held_delay: f32,
+    /// result of the last export or import, shown below the options
+    status: Option<String>,
 }
 
 enum MenuOption {
     Fullscreen,
-    Mute,
+    Audio,
     ShowFPS,
+    ReducedMotion,
+    StickDeadzone,
+    KeyRepeat,
+    RepeatAcceleration,
+    Controls,
+    ExportProgress,
+    ImportProgress,
     Back,
 }
 
 impl Settings {
-    pub fn new(_ctx: &Context, active: bool) -> Self {
+    pub fn new(_ctx: &Context) -> Self {
         let menu_options = vec![
             MenuOption::Fullscreen,
-            MenuOption::Mute,
+            MenuOption::Audio,
             MenuOption::ShowFPS,
+            MenuOption::ReducedMotion,
+            MenuOption::StickDeadzone,
+            MenuOption::KeyRepeat,
+            MenuOption::RepeatAcceleration,
+            MenuOption::Controls,
+            MenuOption::ExportProgress,
+            MenuOption::ImportProgress,
             MenuOption::Back,
         ];
 
         Self {
             menu_options,
             menu_index: 0,
-            move_held_delay: 0.,
-            active,
+            status: None,
+        }
+    }
+
+    /// copies the progress code to the clipboard, and on desktop also writes it to a file that
+    /// can be moved to another computer
+    fn export_progress(ctx: &Context) -> String {
+        clipboard_set(&ctx.save.to_progress_code());
+
+        #[cfg(not(target_family = "wasm"))]
+        return match ctx.save.export_to_file() {
+            Ok(path) => format!("Copied code to clipboard and saved to {}", path.display()),
+            Err(e) => format!("Copied code to clipboard, but {}", e),
+        };
+
+        #[cfg(target_family = "wasm")]
+        "Copied progress code to clipboard".to_string()
+    }
+
+    /// merges in progress from the import file on desktop if present, otherwise from a progress
+    /// code in the clipboard
+    ///
+    /// on web the clipboard can only be read after pasting into the game with Ctrl+V
+    fn import_progress(ctx: &mut Context) -> String {
+        #[cfg(not(target_family = "wasm"))]
+        let imported = if Save::determine_import_path().exists() {
+            Save::import_from_file()
+        } else {
+            Self::save_from_clipboard()
+        };
+
+        #[cfg(target_family = "wasm")]
+        let imported = Self::save_from_clipboard();
+
+        match imported {
+            Ok(save) => import_text(&ctx.save.merge(&save)),
+            Err(e) => format!("Couldn't import: {}", e),
+        }
+    }
+
+    fn save_from_clipboard() -> Result<Save, String> {
+        match clipboard_get() {
+            Some(code) => Save::from_progress_code(&code),
+            None => Err("no progress code to paste, press Ctrl+V first".to_string()),
         }
     }
 
@@ -54,28 +114,64 @@ impl Settings {
             MenuOption::ShowFPS => {
                 format!("Show FPS: {}", settings.show_fps())
             }
-            MenuOption::Mute => format!("Mute: {}", settings.is_muted()),
+            MenuOption::Audio => "Audio".to_string(),
+            MenuOption::ReducedMotion => {
+                format!("Reduced Motion: {}", settings.is_reduced_motion())
+            }
+            MenuOption::StickDeadzone => {
+                format!(
+                    "Stick Deadzone: {}%",
+                    (settings.stick_deadzone() * 100.).round()
+                )
+            }
+            MenuOption::KeyRepeat => {
+                format!("Key Repeat: {}", settings.key_repeat().preset_name())
+            }
+            MenuOption::RepeatAcceleration => {
+                format!(
+                    "Repeat Acceleration: {}",
+                    settings.key_repeat().is_accelerating()
+                )
+            }
+            MenuOption::Controls => "Controls".to_string(),
+            MenuOption::ExportProgress => "Export Progress".to_string(),
+            MenuOption::ImportProgress => "Import Progress".to_string(),
         }
     }
 }
 
+/// what was imported, like "Imported 2 levels, 1 achievement and stats"
+fn import_text(merged: &Merged) -> String {
+    let count = |n: usize, thing: &str| match n {
+        0 => None,
+        1 => Some(format!("1 {}", thing)),
+        n => Some(format!("{} {}s", n, thing)),
+    };
+    let parts: Vec<String> = [
+        count(merged.levels, "level"),
+        count(merged.achievements, "achievement"),
+        merged.stats.then(|| "stats".to_string()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    match parts.as_slice() {
+        [] => "Nothing new to import".to_string(),
+        [part] => format!("Imported {}", part),
+        [rest @ .., last] => format!("Imported {} and {}", rest.join(", "), last),
+    }
+}
+
 impl Scene for Settings {
     fn update(&mut self, ctx: &mut Context) {
-        if self.move_held_delay > 0.0 {
-            self.move_held_delay -= get_frame_time();
-        }
-
-        if action_pressed(Action::Cancel, &ctx.gamepads) {
-            self.active = false;
-            play_sfx(ctx, &ctx.audio.sfx.menu_cancel);
+        if action_pressed(Action::Cancel, ctx) {
+            ctx.pop_scene();
+            play_sfx(ctx, Sfx::MenuCancel);
             return;
         }
 
-        if action_pressed(Action::Up, &ctx.gamepads)
-            || (action_down(Action::Up, &ctx.gamepads) && self.move_held_delay <= 0.)
-        {
-            self.move_held_delay = MOVE_HELD_DELAY;
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
+        if action_repeated(Action::Up, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
 
             if self.menu_index == 0 {
                 self.menu_index = self.menu_options.len() - 1;
@@ -83,11 +179,8 @@ impl Scene for Settings {
                 self.menu_index -= 1;
             }
         }
-        if action_pressed(Action::Down, &ctx.gamepads)
-            || (action_down(Action::Down, &ctx.gamepads) && self.move_held_delay <= 0.)
-        {
-            self.move_held_delay = MOVE_HELD_DELAY;
-            play_sfx(ctx, &ctx.audio.sfx.menu_move);
+        if action_repeated(Action::Down, ctx) {
+            play_sfx(ctx, Sfx::MenuMove);
 
             if self.menu_index == self.menu_options.len() - 1 {
                 self.menu_index = 0;
@@ -96,8 +189,8 @@ impl Scene for Settings {
             }
         }
 
-        if action_pressed(Action::Confirm, &ctx.gamepads) {
-            play_sfx(ctx, &ctx.audio.sfx.menu_select);
+        if action_pressed(Action::Confirm, ctx) {
+            play_sfx(ctx, Sfx::MenuSelect);
 
             let menu_option = self
                 .menu_options
@@ -105,17 +198,40 @@ impl Scene for Settings {
                 .expect("pause menu index out of bounds");
             match menu_option {
                 MenuOption::Back => {
-                    self.active = false;
+                    ctx.pop_scene();
                 }
                 MenuOption::Fullscreen => {
                     ctx.settings.toggle_fullscreen();
                 }
-                MenuOption::Mute => {
-                    ctx.settings.toggle_mute();
+                MenuOption::Audio => {
+                    let audio_settings = AudioSettings::new(ctx);
+                    ctx.push_scene(audio_settings);
                 }
                 MenuOption::ShowFPS => {
                     ctx.settings.toggle_show_fps();
                 }
+                MenuOption::ReducedMotion => {
+                    ctx.settings.toggle_reduced_motion();
+                }
+                MenuOption::StickDeadzone => {
+                    ctx.settings.cycle_stick_deadzone();
+                }
+                MenuOption::KeyRepeat => {
+                    ctx.settings.cycle_key_repeat();
+                }
+                MenuOption::RepeatAcceleration => {
+                    ctx.settings.toggle_repeat_acceleration();
+                }
+                MenuOption::Controls => {
+                    let controls = Controls::new(ctx);
+                    ctx.push_scene(controls);
+                }
+                MenuOption::ExportProgress => {
+                    self.status = Some(Self::export_progress(ctx));
+                }
+                MenuOption::ImportProgress => {
+                    self.status = Some(Self::import_progress(ctx));
+                }
             }
         }
     }
@@ -131,10 +247,46 @@ impl Scene for Settings {
                 ctx,
                 text.as_str(),
                 X_INSET,
-                200. + (i as f32 * 40.),
+                200. + (i as f32 * 36.),
                 Size::Medium,
                 color,
             );
         }
+
+        if let Some(status) = &self.status {
+            let status = status.clone();
+            draw_text(
+                ctx,
+                status.as_str(),
+                X_INSET,
+                VIRTUAL_HEIGHT - 80.,
+                Size::Small,
+                BLUE,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_text() {
+        let merged = |levels, achievements, stats| Merged {
+            levels,
+            achievements,
+            stats,
+        };
+        assert_eq!(import_text(&merged(0, 0, false)), "Nothing new to import");
+        assert_eq!(import_text(&merged(1, 0, false)), "Imported 1 level");
+        assert_eq!(
+            import_text(&merged(0, 2, true)),
+            "Imported 2 achievements and stats"
+        );
+        assert_eq!(
+            import_text(&merged(3, 1, true)),
+            "Imported 3 levels, 1 achievement and stats"
+        );
     }
 }
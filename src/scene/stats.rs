@@ -0,0 +1,156 @@
+use macroquad::color::{RED, WHITE};
+
+use super::Scene;
+use crate::audio::{play_sfx, Sfx};
+use crate::color::BLUE;
+use crate::consts::{TITLE_Y_INSET, VIRTUAL_HEIGHT, X_INSET};
+use crate::input::{action_pressed, Action, InputLayer};
+use crate::level::pack::{Difficulty, Pack};
+use crate::text::Size;
+use crate::{context::Context, text::draw_text};
+
+/// how many of the most retried levels to list
+const MOST_RETRIED_COUNT: usize = 5;
+/// where the second column of stats starts
+const RIGHT_COLUMN_X: f32 = 700.;
+const LINE_HEIGHT: f32 = 32.;
+
+/// scene summarizing the active profile's play across all packs
+pub struct Stats {
+    /// totals and completion counts, shown on the left
+    summary: Vec<String>,
+    /// per-pack and per-difficulty completion, shown on the right
+    completion: Vec<String>,
+    most_retried: Vec<String>,
+}
+
+impl Stats {
+    /// calculates the stats from the save for the packs
+    pub fn new(ctx: &Context, packs: &[Pack]) -> Self {
+        let completed_count = |pack: &Pack| -> usize {
+            pack.levels
+                .iter()
+                .filter(|l| ctx.save.is_level_complete(&pack.slug, &l.title))
+                .count()
+        };
+
+        let stats = ctx.save.stats();
+        let levels_completed: usize = packs.iter().map(completed_count).sum();
+        let levels_total: usize = packs.iter().map(|p| p.levels.len()).sum();
+        let summary = vec![
+            format!("Steps: {}", stats.steps),
+            format!("Pushes: {}", stats.pushes),
+            format!("Play Time: {}", format_play_time(stats.play_time)),
+            format!("Levels Completed: {} / {}", levels_completed, levels_total),
+        ];
+
+        let mut completion: Vec<String> = packs
+            .iter()
+            .map(|p| format!("{}: {} / {}", p.title, completed_count(p), p.levels.len()))
+            .collect();
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            let difficulty_packs: Vec<&Pack> = packs
+                .iter()
+                .filter(|p| p.difficulty == difficulty)
+                .collect();
+            if difficulty_packs.is_empty() {
+                continue;
+            }
+            completion.push(format!(
+                "{}: {} / {}",
+                difficulty,
+                difficulty_packs
+                    .iter()
+                    .map(|p| completed_count(p))
+                    .sum::<usize>(),
+                difficulty_packs
+                    .iter()
+                    .map(|p| p.levels.len())
+                    .sum::<usize>(),
+            ));
+        }
+
+        let most_retried = ctx
+            .save
+            .most_retried_levels(MOST_RETRIED_COUNT)
+            .iter()
+            .map(|l| {
+                let pack_title = packs
+                    .iter()
+                    .find(|p| p.slug == l.pack)
+                    .map_or(l.pack.as_str(), |p| p.title.as_str());
+                format!("{} ({}): {} attempts", l.level, pack_title, l.attempts)
+            })
+            .collect();
+
+        Self {
+            summary,
+            completion,
+            most_retried,
+        }
+    }
+}
+
+/// formats seconds as hours and minutes, or minutes and seconds when under an hour
+fn format_play_time(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    if seconds >= 3600 {
+        format!("{}h {:02}m", seconds / 3600, (seconds % 3600) / 60)
+    } else {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+impl Scene for Stats {
+    fn update(&mut self, ctx: &mut Context) {
+        if action_pressed(Action::Cancel, ctx) || action_pressed(Action::Confirm, ctx) {
+            play_sfx(ctx, Sfx::MenuCancel);
+            ctx.pop_scene();
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) {
+        let title = format!("Stats for {}", ctx.profiles.active().name);
+        draw_text(ctx, &title, X_INSET, TITLE_Y_INSET, Size::Large, WHITE);
+
+        let mut y = 200.;
+        for line in &self.summary {
+            draw_text(ctx, line, X_INSET, y, Size::Small, WHITE);
+            y += LINE_HEIGHT;
+        }
+
+        y += LINE_HEIGHT;
+        draw_text(ctx, "Most Retried", X_INSET, y, Size::Medium, BLUE);
+        y += LINE_HEIGHT;
+        if self.most_retried.is_empty() {
+            draw_text(ctx, "Nothing yet", X_INSET, y, Size::Small, WHITE);
+        }
+        for line in &self.most_retried {
+            draw_text(ctx, line, X_INSET, y, Size::Small, WHITE);
+            y += LINE_HEIGHT;
+        }
+
+        let mut y = 200.;
+        draw_text(ctx, "Completed", RIGHT_COLUMN_X, y, Size::Medium, BLUE);
+        y += LINE_HEIGHT;
+        for line in &self.completion {
+            draw_text(ctx, line, RIGHT_COLUMN_X, y, Size::Small, WHITE);
+            y += LINE_HEIGHT;
+        }
+
+        let controls = ctx.settings.controls();
+        let hint = format!(
+            "Press {} or {} to return",
+            controls.hint(InputLayer::Menu, Action::Confirm),
+            controls.hint(InputLayer::Menu, Action::Cancel)
+        );
+        draw_text(
+            ctx,
+            hint.as_str(),
+            X_INSET,
+            VIRTUAL_HEIGHT - 80.,
+            Size::Medium,
+            RED,
+        );
+    }
+}
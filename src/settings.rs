@@ -1,45 +1,92 @@
-#[cfg(not(target_family = "wasm"))]
-use crate::fs;
+use crate::audio::AudioBus;
+use crate::input::repeat::KeyRepeat;
+use crate::input::stick::DEFAULT_STICK_DEADZONE;
+use crate::input::InputMap;
+use crate::storage::SharedStorage;
 use macroquad::window::set_fullscreen;
 use serde::{Deserialize, Serialize};
-#[cfg(not(target_family = "wasm"))]
-use std::path::PathBuf;
 
 /// user-set options to customize the experience to their liking
 #[derive(Deserialize, Serialize)]
 pub struct Settings {
     /// whether or not audio should play
     mute: Option<bool>,
+    /// how loud everything is, from 0 to 1
+    master_volume: Option<f32>,
+    /// how loud sound effects are, from 0 to 1, before the master volume
+    sfx_volume: Option<f32>,
+    /// how loud music is, from 0 to 1, before the master volume
+    music_volume: Option<f32>,
     /// whether or not the window should take up the entire screen
     fullscreen: Option<bool>,
     /// whether or not to show the frames per second in the upper left area of the screen
     show_fps: Option<bool>,
+    /// whether or not scene transitions fade instead of sliding and wiping across the screen
+    reduced_motion: Option<bool>,
+    /// how far the left stick has to be pushed to move, from 0 to 1
+    stick_deadzone: Option<f32>,
+    /// how moving repeats while a direction is held
+    #[serde(default)]
+    key_repeat: KeyRepeat,
+    /// the keys and gamepad buttons for each action
+    #[serde(default)]
+    controls: InputMap,
+    /// where the settings are written
+    #[serde(skip)]
+    storage: Option<SharedStorage>,
+    /// muted for this session only, like with the `--mute` arg, without changing the setting
+    #[serde(skip)]
+    session_mute: bool,
+    /// fullscreen for this session only, like with the `--fullscreen` arg
+    #[serde(skip)]
+    session_fullscreen: bool,
 }
 
-#[cfg(target_family = "wasm")]
-const FULLSCREEN: &str = "fullscreen";
-#[cfg(target_family = "wasm")]
-const MUTE: &str = "mute";
-#[cfg(target_family = "wasm")]
-const SHOW_FPS: &str = "show_fps";
-#[cfg(not(target_family = "wasm"))]
 const SETTINGS_FILE: &str = "settings.toml";
 
+/// the keys each setting was stored under on web before they were kept together, moved into the
+/// settings the first time they're loaded
+const LEGACY_WEB_KEYS: [&str; 3] = ["fullscreen", "mute", "show_fps"];
+
+/// how much the volume goes up or down with each step in the settings
+const VOLUME_STEP: f32 = 0.1;
+
+/// the stick deadzones that can be picked from the settings
+const STICK_DEADZONES: [f32; 7] = [0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
+
 impl Settings {
     fn default() -> Self {
         Settings {
             fullscreen: Some(false),
             mute: Some(false),
+            master_volume: Some(1.),
+            sfx_volume: Some(1.),
+            music_volume: Some(0.8),
             show_fps: Some(false),
+            reduced_motion: Some(false),
+            stick_deadzone: Some(DEFAULT_STICK_DEADZONE),
+            key_repeat: KeyRepeat::default(),
+            controls: InputMap::default(),
+            storage: None,
+            session_mute: false,
+            session_fullscreen: false,
         }
     }
 
-    pub fn load() -> Self {
-        #[cfg(target_family = "wasm")]
-        let settings = Self::load_wasm();
-
-        #[cfg(not(target_family = "wasm"))]
-        let settings = Self::load_desktop();
+    pub fn load(storage: SharedStorage) -> Self {
+        let stored = storage
+            .borrow()
+            .read(SETTINGS_FILE)
+            .expect("couldn't read settings file");
+        let stored_is_missing = stored.is_none();
+        let mut settings = match stored {
+            Some(toml_str) => toml::from_str(toml_str.as_str()).unwrap(),
+            None => Self::default(),
+        };
+        settings.storage = Some(storage.clone());
+        if stored_is_missing {
+            settings.migrate_legacy_web_keys(&storage);
+        }
 
         if settings.is_fullscreen() {
             set_fullscreen(settings.is_fullscreen());
@@ -48,51 +95,153 @@ impl Settings {
         settings
     }
 
-    #[cfg(target_family = "wasm")]
-    fn load_wasm() -> Self {
-        let mut settings = Self::default();
-        let storage = &mut quad_storage::STORAGE.lock().unwrap();
-        if let Some(storage_fullscreen) = storage.get(FULLSCREEN) {
-            settings.fullscreen = Some(storage_fullscreen == "true");
+    /// picks up the settings from before they were stored together, saving them the new way and
+    /// removing the old keys so it only happens once
+    fn migrate_legacy_web_keys(&mut self, storage: &SharedStorage) {
+        let mut found = false;
+        for key in LEGACY_WEB_KEYS {
+            let Ok(Some(value)) = storage.borrow().read(key) else {
+                continue;
+            };
+            found = true;
+            let value = Some(value == "true");
+            match key {
+                "fullscreen" => self.fullscreen = value,
+                "mute" => self.mute = value,
+                _ => self.show_fps = value,
+            }
         }
-        if let Some(storage_mute) = storage.get(MUTE) {
-            settings.mute = Some(storage_mute == "true");
+        if !found {
+            return;
         }
-        if let Some(storage_show_fps) = storage.get(SHOW_FPS) {
-            settings.mute = Some(storage_show_fps == "true");
+
+        self.save_settings();
+        for key in LEGACY_WEB_KEYS {
+            storage
+                .borrow_mut()
+                .remove(key)
+                .expect("unable to remove old setting");
         }
-        settings
     }
 
-    #[cfg(not(target_family = "wasm"))]
-    fn load_desktop() -> Self {
-        let settings_path = Self::determine_settings_path();
-
-        if settings_path.exists() {
-            let toml_str =
-                std::fs::read_to_string(settings_path).expect("couldn't read settings file");
-            let settings: Settings = toml::from_str(toml_str.as_str()).unwrap();
-            settings
-        } else {
-            Self::default()
-        }
+    /// turns fullscreen and mute on until the game is closed, without saving them; toggling
+    /// either clears the override
+    pub fn override_for_session(&mut self, fullscreen: bool, mute: bool) {
+        self.session_fullscreen = fullscreen;
+        self.session_mute = mute;
     }
 
     pub fn is_fullscreen(&self) -> bool {
-        self.fullscreen
-            .unwrap_or(Self::default().fullscreen.unwrap())
+        self.session_fullscreen
+            || self
+                .fullscreen
+                .unwrap_or(Self::default().fullscreen.unwrap())
     }
 
     pub fn is_muted(&self) -> bool {
-        self.mute.unwrap_or(Self::default().mute.unwrap())
+        self.session_mute || self.mute.unwrap_or(Self::default().mute.unwrap())
+    }
+
+    /// the volume set for the bus on its own, from 0 to 1
+    pub fn volume(&self, bus: AudioBus) -> f32 {
+        let defaults = Self::default();
+        let (volume, default) = match bus {
+            AudioBus::Master => (self.master_volume, defaults.master_volume),
+            AudioBus::Sfx => (self.sfx_volume, defaults.sfx_volume),
+            AudioBus::Music => (self.music_volume, defaults.music_volume),
+        };
+        volume.unwrap_or(default.unwrap()).clamp(0., 1.)
+    }
+
+    /// how loud the bus actually plays, taking the master volume and mute into account
+    pub fn output_volume(&self, bus: AudioBus) -> f32 {
+        if self.is_muted() {
+            return 0.;
+        }
+        match bus {
+            AudioBus::Master => self.volume(AudioBus::Master),
+            _ => self.volume(AudioBus::Master) * self.volume(bus),
+        }
+    }
+
+    /// turns the bus up or down by this many steps, stopping at silent and full volume
+    pub fn change_volume(&mut self, bus: AudioBus, steps: i32) -> f32 {
+        // rounded to whole steps so repeated changes don't drift
+        let current = (self.volume(bus) / VOLUME_STEP).round() as i32;
+        let max = (1. / VOLUME_STEP).round() as i32;
+        let volume = Some((current + steps).clamp(0, max) as f32 * VOLUME_STEP);
+        match bus {
+            AudioBus::Master => self.master_volume = volume,
+            AudioBus::Sfx => self.sfx_volume = volume,
+            AudioBus::Music => self.music_volume = volume,
+        }
+
+        self.save_settings();
+        self.volume(bus)
     }
 
     pub fn show_fps(&self) -> bool {
         self.show_fps.unwrap_or(Self::default().show_fps.unwrap())
     }
 
+    pub fn is_reduced_motion(&self) -> bool {
+        self.reduced_motion
+            .unwrap_or(Self::default().reduced_motion.unwrap())
+    }
+
+    pub fn stick_deadzone(&self) -> f32 {
+        self.stick_deadzone
+            .unwrap_or(Self::default().stick_deadzone.unwrap())
+            .clamp(
+                STICK_DEADZONES[0],
+                STICK_DEADZONES[STICK_DEADZONES.len() - 1],
+            )
+    }
+
+    /// moves on to the next larger deadzone, going back to the smallest after the largest
+    pub fn cycle_stick_deadzone(&mut self) -> f32 {
+        let current = self.stick_deadzone();
+        let next = STICK_DEADZONES
+            .into_iter()
+            .find(|deadzone| *deadzone > current + 0.01)
+            .unwrap_or(STICK_DEADZONES[0]);
+        self.stick_deadzone = Some(next);
+
+        self.save_settings();
+        self.stick_deadzone()
+    }
+
+    pub fn key_repeat(&self) -> &KeyRepeat {
+        &self.key_repeat
+    }
+
+    /// switches between the slow, normal and fast key repeat
+    pub fn cycle_key_repeat(&mut self) -> &KeyRepeat {
+        self.key_repeat = self.key_repeat.next_preset();
+
+        self.save_settings();
+        self.key_repeat()
+    }
+
+    pub fn toggle_repeat_acceleration(&mut self) -> bool {
+        self.key_repeat = self.key_repeat.toggle_acceleration();
+
+        self.save_settings();
+        self.key_repeat.is_accelerating()
+    }
+
+    pub fn controls(&self) -> &InputMap {
+        &self.controls
+    }
+
+    pub fn set_controls(&mut self, controls: InputMap) {
+        self.controls = controls;
+        self.save_settings();
+    }
+
     pub fn toggle_mute(&mut self) -> bool {
         self.mute = Some(!self.is_muted());
+        self.session_mute = false;
 
         self.save_settings();
         self.mute.unwrap()
@@ -100,6 +249,7 @@ impl Settings {
 
     pub fn toggle_fullscreen(&mut self) -> bool {
         self.fullscreen = Some(!self.is_fullscreen());
+        self.session_fullscreen = false;
         set_fullscreen(self.is_fullscreen());
         self.save_settings();
         self.is_fullscreen()
@@ -112,28 +262,182 @@ impl Settings {
         self.show_fps()
     }
 
-    #[cfg(target_family = "wasm")]
-    fn save_settings(&self) {
-        let storage = &mut quad_storage::STORAGE.lock().unwrap();
-        storage.set(MUTE, self.is_muted().to_string().as_str());
-        storage.set(FULLSCREEN, self.is_fullscreen().to_string().as_str());
-        storage.set(SHOW_FPS, self.show_fps().to_string().as_str());
+    pub fn toggle_reduced_motion(&mut self) -> bool {
+        self.reduced_motion = Some(!self.is_reduced_motion());
+
+        self.save_settings();
+        self.is_reduced_motion()
     }
 
-    #[cfg(not(target_family = "wasm"))]
     fn save_settings(&self) {
-        let toml = toml::to_string(self).unwrap();
-        std::fs::write(Self::determine_settings_path(), toml)
-            .expect("unable to write settings file");
-    }
-
-    #[cfg(not(target_family = "wasm"))]
-    fn determine_settings_path() -> PathBuf {
-        let project_dirs = fs::project_dirs();
-        let settings_dir = project_dirs.config_local_dir();
-        std::fs::create_dir_all(settings_dir).unwrap();
-        let mut settings_path = PathBuf::from(settings_dir);
-        settings_path.push(SETTINGS_FILE);
-        settings_path
+        if let Some(storage) = &self.storage {
+            let toml = toml::to_string(self).unwrap();
+            storage
+                .borrow_mut()
+                .write(SETTINGS_FILE, &toml)
+                .expect("unable to write settings file");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Action, Binding, InputLayer};
+    use crate::storage::{share, MemoryStorage};
+    use gamepads::Button;
+    use macroquad::input::KeyCode;
+
+    #[test]
+    fn test_settings_persist() {
+        let storage = share(MemoryStorage::default());
+        let mut settings = Settings::load(storage.clone());
+        assert!(!settings.show_fps());
+        assert!(!settings.is_muted());
+
+        settings.toggle_show_fps();
+
+        // each option is loaded into its own field, regardless of platform
+        let settings = Settings::load(storage);
+        assert!(settings.show_fps());
+        assert!(!settings.is_muted());
+    }
+
+    #[test]
+    fn test_migrates_legacy_web_keys() {
+        let storage = share(MemoryStorage::default());
+        storage.borrow_mut().write("mute", "true").unwrap();
+        storage.borrow_mut().write("show_fps", "true").unwrap();
+
+        let settings = Settings::load(storage.clone());
+        assert!(settings.is_muted());
+        assert!(settings.show_fps());
+        assert!(!settings.is_fullscreen());
+        assert_eq!(storage.borrow().read("mute").unwrap(), None);
+        assert_eq!(storage.borrow().read("show_fps").unwrap(), None);
+
+        let settings = Settings::load(storage.clone());
+        assert!(settings.is_muted());
+        assert!(settings.show_fps());
+    }
+
+    #[test]
+    fn test_session_override_is_not_saved() {
+        let storage = share(MemoryStorage::default());
+        let mut settings = Settings::load(storage.clone());
+        settings.override_for_session(false, true);
+        assert!(settings.is_muted());
+
+        settings.toggle_show_fps();
+        assert!(!Settings::load(storage.clone()).is_muted());
+
+        // toggling takes over from the override
+        settings.toggle_mute();
+        assert!(!settings.is_muted());
+        settings.toggle_mute();
+        assert!(Settings::load(storage).is_muted());
+    }
+
+    #[test]
+    fn test_volume() {
+        let storage = share(MemoryStorage::default());
+        let mut settings = Settings::load(storage.clone());
+        assert_eq!(settings.volume(AudioBus::Sfx), 1.);
+        assert_eq!(settings.change_volume(AudioBus::Sfx, 1), 1.);
+        settings.change_volume(AudioBus::Master, -5);
+        settings.change_volume(AudioBus::Sfx, -2);
+
+        let mut settings = Settings::load(storage);
+        assert_eq!(settings.volume(AudioBus::Master), 0.5);
+        assert_eq!(settings.output_volume(AudioBus::Sfx), 0.4);
+        assert_eq!(settings.change_volume(AudioBus::Music, -20), 0.);
+
+        settings.toggle_mute();
+        assert_eq!(settings.output_volume(AudioBus::Master), 0.);
+        assert_eq!(settings.volume(AudioBus::Master), 0.5);
+    }
+
+    #[test]
+    fn test_cycle_stick_deadzone() {
+        let storage = share(MemoryStorage::default());
+        let mut settings = Settings::load(storage.clone());
+        assert_eq!(settings.stick_deadzone(), 0.5);
+        assert_eq!(settings.cycle_stick_deadzone(), 0.6);
+        settings.cycle_stick_deadzone();
+        settings.cycle_stick_deadzone();
+        assert_eq!(settings.cycle_stick_deadzone(), 0.2);
+        assert_eq!(Settings::load(storage).stick_deadzone(), 0.2);
+    }
+
+    #[test]
+    fn test_key_repeat_persists() {
+        let storage = share(MemoryStorage::default());
+        let mut settings = Settings::load(storage.clone());
+        settings.cycle_key_repeat();
+        settings.toggle_repeat_acceleration();
+
+        let key_repeat = *Settings::load(storage).key_repeat();
+        assert_eq!(key_repeat.preset_name(), "Fast");
+        assert!(key_repeat.is_accelerating());
+    }
+
+    #[test]
+    fn test_controls_persist() {
+        let storage = share(MemoryStorage::default());
+        let mut settings = Settings::load(storage.clone());
+        let mut controls = settings.controls().clone();
+        controls
+            .toggle_binding(
+                InputLayer::Gameplay,
+                Action::Pause,
+                Binding::Button(Button::Mode),
+            )
+            .unwrap();
+        settings.set_controls(controls.clone());
+
+        assert_eq!(Settings::load(storage).controls(), &controls);
+    }
+
+    #[test]
+    fn test_unknown_controls_are_left_out() {
+        let storage = share(MemoryStorage::default());
+        storage
+            .borrow_mut()
+            .write(
+                SETTINGS_FILE,
+                "show_fps = true\n\n[controls.menu]\nup = [\"key:I\", \"key:Unknown\"]\ndown = [\"pad:Nope\"]\njump = [\"key:J\"]\n\n[controls.somewhere]\nup = [\"key:U\"]\n",
+            )
+            .unwrap();
+        let settings = Settings::load(storage);
+        assert!(settings.show_fps());
+        assert_eq!(
+            settings.controls().bindings(InputLayer::Menu, Action::Up),
+            [Binding::Key(KeyCode::I)]
+        );
+        assert_eq!(
+            settings.controls().bindings(InputLayer::Menu, Action::Down),
+            InputMap::default().bindings(InputLayer::Menu, Action::Down)
+        );
+    }
+
+    #[test]
+    fn test_missing_controls_use_defaults() {
+        let storage = share(MemoryStorage::default());
+        storage
+            .borrow_mut()
+            .write(
+                SETTINGS_FILE,
+                "mute = true\n\n[controls.menu]\nup = [\"key:I\"]\n",
+            )
+            .unwrap();
+        let settings = Settings::load(storage);
+        assert_eq!(
+            settings.controls().bindings(InputLayer::Menu, Action::Up),
+            [Binding::Key(KeyCode::I)]
+        );
+        assert_eq!(
+            settings.controls().bindings(InputLayer::Menu, Action::Down),
+            InputMap::default().bindings(InputLayer::Menu, Action::Down)
+        );
     }
 }
@@ -0,0 +1,182 @@
+#[cfg(not(target_family = "wasm"))]
+use crate::fs;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Debug;
+#[cfg(not(target_family = "wasm"))]
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// somewhere to persist data between sessions, like the save and settings
+///
+/// keys are file names (`save.ron`, `settings.toml`) so that every backend lays out the data the
+/// same way
+pub trait Storage: Debug {
+    /// returns the value written for the key, or `None` if nothing has been written yet
+    fn read(&self, key: &str) -> Result<Option<String>, String>;
+    fn write(&mut self, key: &str, value: &str) -> Result<(), String>;
+    /// removes the value for the key; removing a key that doesn't exist is fine
+    fn remove(&mut self, key: &str) -> Result<(), String>;
+}
+
+/// storage that's shared between everything that persists data, like the profiles and save
+pub type SharedStorage = Rc<RefCell<dyn Storage>>;
+
+pub fn share(storage: impl Storage + 'static) -> SharedStorage {
+    Rc::new(RefCell::new(storage))
+}
+
+/// where progress (profiles and saves) is persisted for the current platform
+pub fn data_storage() -> SharedStorage {
+    #[cfg(not(target_family = "wasm"))]
+    return share(FileStorage::new(fs::data_dir()));
+
+    #[cfg(target_family = "wasm")]
+    share(QuadStorage)
+}
+
+/// where settings are persisted for the current platform
+pub fn config_storage() -> SharedStorage {
+    #[cfg(not(target_family = "wasm"))]
+    return share(FileStorage::new(fs::config_dir()));
+
+    #[cfg(target_family = "wasm")]
+    share(QuadStorage)
+}
+
+/// stores each key as a file in a directory, used on desktop
+#[cfg(not(target_family = "wasm"))]
+#[derive(Debug)]
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl FileStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// the file that the key is stored in
+    pub fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Storage for FileStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, String> {
+        let path = self.path_for(key);
+        if !path.exists() {
+            return Ok(None);
+        }
+        std::fs::read_to_string(&path)
+            .map(Some)
+            .map_err(|e| format!("couldn't read {}: {}", path.display(), e))
+    }
+
+    fn write(&mut self, key: &str, value: &str) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("couldn't create {}: {}", self.dir.display(), e))?;
+        let path = self.path_for(key);
+        std::fs::write(&path, value)
+            .map_err(|e| format!("couldn't write {}: {}", path.display(), e))
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), String> {
+        let path = self.path_for(key);
+        if !path.exists() {
+            return Ok(());
+        }
+        std::fs::remove_file(&path)
+            .map_err(|e| format!("couldn't remove {}: {}", path.display(), e))
+    }
+}
+
+/// stores each key in the browser's local storage, used on web
+///
+/// the extension is dropped from the key, so `save.ron` is stored as `save` like it was before
+/// this existed
+#[cfg(target_family = "wasm")]
+#[derive(Debug)]
+pub struct QuadStorage;
+
+#[cfg(target_family = "wasm")]
+impl QuadStorage {
+    fn local_storage_key(key: &str) -> &str {
+        key.split('.').next().unwrap_or(key)
+    }
+}
+
+#[cfg(target_family = "wasm")]
+impl Storage for QuadStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, String> {
+        let storage = quad_storage::STORAGE.lock().unwrap();
+        Ok(storage.get(Self::local_storage_key(key)))
+    }
+
+    fn write(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let storage = &mut quad_storage::STORAGE.lock().unwrap();
+        storage.set(Self::local_storage_key(key), value);
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), String> {
+        let storage = &mut quad_storage::STORAGE.lock().unwrap();
+        storage.remove(Self::local_storage_key(key));
+        Ok(())
+    }
+}
+
+/// keeps everything in memory and forgets it when dropped, useful for tests
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    entries: HashMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn write(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.entries.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), String> {
+        self.entries.remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise(storage: &mut dyn Storage) {
+        assert_eq!(storage.read("save.ron").unwrap(), None);
+        storage.write("save.ron", "hello").unwrap();
+        assert_eq!(storage.read("save.ron").unwrap(), Some("hello".to_string()));
+        storage.write("save.ron", "goodbye").unwrap();
+        assert_eq!(
+            storage.read("save.ron").unwrap(),
+            Some("goodbye".to_string())
+        );
+        storage.remove("save.ron").unwrap();
+        assert_eq!(storage.read("save.ron").unwrap(), None);
+        storage.remove("save.ron").unwrap();
+    }
+
+    #[test]
+    fn test_memory_storage() {
+        exercise(&mut MemoryStorage::default());
+    }
+
+    #[test]
+    fn test_file_storage() {
+        let dir = std::env::temp_dir().join(format!("sokoworld-storage-{}", std::process::id()));
+        exercise(&mut FileStorage::new(&dir));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::VecDeque;
+
+use macroquad::color::{Color, WHITE};
+use macroquad::shapes::draw_rectangle;
+
+use crate::color::DARKGRAY;
+use crate::consts::VIRTUAL_WIDTH;
+use crate::context::Context;
+use crate::text::{draw_text, Size};
+
+/// how long in seconds each toast is shown for
+const TOAST_DURATION: f32 = 3.;
+const TOAST_WIDTH: f32 = 480.;
+const TOAST_HEIGHT: f32 = 56.;
+const TOAST_MARGIN: f32 = 24.;
+
+/// short messages shown one after another in the corner of the screen, like unlocked achievements
+pub struct Toasts {
+    queue: VecDeque<String>,
+    /// seconds left to show the current toast
+    remaining: f32,
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            remaining: TOAST_DURATION,
+        }
+    }
+
+    pub fn push(&mut self, text: String) {
+        if self.queue.is_empty() {
+            self.remaining = TOAST_DURATION;
+        }
+        self.queue.push_back(text);
+    }
+
+    /// counts down the current toast, moving on to the next when time is up
+    pub fn update(&mut self, dt: f32) {
+        if self.queue.is_empty() {
+            return;
+        }
+        self.remaining -= dt;
+        if self.remaining <= 0. {
+            self.queue.pop_front();
+            self.remaining = TOAST_DURATION;
+        }
+    }
+
+    pub fn current(&self) -> Option<&String> {
+        self.queue.front()
+    }
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// draws the current toast, if any, in the upper right of the screen
+pub fn draw_toast(ctx: &mut Context) {
+    let Some(text) = ctx.toasts.current().cloned() else {
+        return;
+    };
+
+    let x = VIRTUAL_WIDTH - TOAST_WIDTH - TOAST_MARGIN;
+    let y = TOAST_MARGIN;
+    draw_rectangle(
+        x,
+        y,
+        TOAST_WIDTH,
+        TOAST_HEIGHT,
+        Color { a: 0.9, ..DARKGRAY },
+    );
+    draw_text(ctx, &text, x + 16., y + 36., Size::Small, WHITE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_show_in_order() {
+        let mut toasts = Toasts::new();
+        toasts.push("one".to_string());
+        toasts.push("two".to_string());
+        assert_eq!(toasts.current().unwrap(), "one");
+
+        toasts.update(TOAST_DURATION / 2.);
+        assert_eq!(toasts.current().unwrap(), "one");
+        toasts.update(TOAST_DURATION / 2.);
+        assert_eq!(toasts.current().unwrap(), "two");
+        toasts.update(TOAST_DURATION);
+        assert!(toasts.current().is_none());
+    }
+}
@@ -0,0 +1,146 @@
+use macroquad::color::Color;
+use macroquad::shapes::draw_rectangle;
+
+use crate::color::DARKGRAY;
+use crate::consts::{VIRTUAL_HEIGHT, VIRTUAL_WIDTH};
+use crate::scene::SceneChange;
+
+/// seconds it takes to cover the screen, and then again to uncover it
+const TRANSITION_HALF_DURATION: f32 = 0.2;
+
+/// how the screen is covered up while the scene changes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransitionKind {
+    /// no transition, the scene changes right away
+    Cut,
+    Fade,
+    /// a panel slides in from the right and out to the left
+    Slide,
+    /// the screen closes from the top and bottom, like when moving on to the next level
+    Wipe,
+}
+
+impl TransitionKind {
+    /// the kind to use when the player wants less motion on screen, which fades instead of
+    /// moving anything across it
+    pub fn with_reduced_motion(self) -> Self {
+        match self {
+            TransitionKind::Slide | TransitionKind::Wipe => TransitionKind::Fade,
+            kind => kind,
+        }
+    }
+}
+
+/// covers the screen, makes the scene changes once it's covered, and then uncovers it
+pub struct Transition {
+    kind: TransitionKind,
+    elapsed: f32,
+    /// made once the screen is covered
+    pending: Vec<SceneChange>,
+}
+
+impl Transition {
+    pub fn new(kind: TransitionKind, pending: Vec<SceneChange>) -> Self {
+        Self {
+            kind,
+            elapsed: 0.,
+            pending,
+        }
+    }
+
+    /// moves the transition along by dt, returning the scene changes to make once the screen is
+    /// covered
+    pub fn update(&mut self, dt: f32) -> Vec<SceneChange> {
+        self.elapsed += dt;
+        if self.is_covering() {
+            vec![]
+        } else {
+            std::mem::take(&mut self.pending)
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= TRANSITION_HALF_DURATION * 2.
+    }
+
+    fn is_covering(&self) -> bool {
+        self.elapsed < TRANSITION_HALF_DURATION
+    }
+
+    /// how much of the screen is covered, from 0 to 1
+    fn coverage(&self) -> f32 {
+        let progress = self.elapsed / TRANSITION_HALF_DURATION;
+        if progress <= 1. {
+            progress
+        } else {
+            (2. - progress).max(0.)
+        }
+    }
+
+    /// draws over the scenes, meant for the render target
+    pub fn draw(&self) {
+        let coverage = self.coverage();
+        match self.kind {
+            TransitionKind::Cut => {}
+            TransitionKind::Fade => {
+                let color = Color {
+                    a: coverage,
+                    ..DARKGRAY
+                };
+                draw_rectangle(0., 0., VIRTUAL_WIDTH, VIRTUAL_HEIGHT, color);
+            }
+            TransitionKind::Slide => {
+                let width = VIRTUAL_WIDTH * coverage;
+                // comes in from the right while covering, and leaves to the left after
+                let x = if self.is_covering() {
+                    VIRTUAL_WIDTH - width
+                } else {
+                    0.
+                };
+                draw_rectangle(x, 0., width, VIRTUAL_HEIGHT, DARKGRAY);
+            }
+            TransitionKind::Wipe => {
+                let height = VIRTUAL_HEIGHT / 2. * coverage;
+                draw_rectangle(0., 0., VIRTUAL_WIDTH, height, DARKGRAY);
+                draw_rectangle(0., VIRTUAL_HEIGHT - height, VIRTUAL_WIDTH, height, DARKGRAY);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_scenes_once_covered() {
+        let mut transition = Transition::new(TransitionKind::Fade, vec![SceneChange::Pop]);
+        assert!(transition.update(TRANSITION_HALF_DURATION / 2.).is_empty());
+        assert_eq!(transition.coverage(), 0.5);
+
+        assert_eq!(transition.update(TRANSITION_HALF_DURATION).len(), 1);
+        assert_eq!(transition.coverage(), 0.5);
+        assert!(!transition.is_done());
+
+        // the changes are only made once
+        assert!(transition.update(TRANSITION_HALF_DURATION).is_empty());
+        assert!(transition.is_done());
+        assert_eq!(transition.coverage(), 0.);
+    }
+
+    #[test]
+    fn test_reduced_motion() {
+        assert_eq!(
+            TransitionKind::Slide.with_reduced_motion(),
+            TransitionKind::Fade
+        );
+        assert_eq!(
+            TransitionKind::Wipe.with_reduced_motion(),
+            TransitionKind::Fade
+        );
+        assert_eq!(
+            TransitionKind::Cut.with_reduced_motion(),
+            TransitionKind::Cut
+        );
+    }
+}
@@ -6,12 +6,10 @@ fn test_packs_are_valid() {
     let paths = std::fs::read_dir("./assets/packs/").unwrap();
 
     for path in paths {
-        let file_string =
-            std::fs::read_to_string(path.unwrap().path()).expect("couldn't read file");
-        let pack = sokoworld::level::pack::Pack {
-            file: None,
-            ..toml::from_str(file_string.as_str()).unwrap()
-        };
+        let path = path.unwrap().path();
+        let file_string = std::fs::read_to_string(&path).expect("couldn't read file");
+        let pack = sokoworld::level::pack::Pack::parse(file_string.as_str())
+            .unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
         assert!(!pack.title.is_empty());
 
         for level in pack.levels {
@@ -20,3 +18,19 @@ fn test_packs_are_valid() {
         }
     }
 }
+
+#[test]
+// ensures the manifest used by the web build lists every bundled pack
+fn test_manifest_lists_every_pack() {
+    let manifest_str = std::fs::read_to_string("./assets/packs.toml").unwrap();
+    let mut manifest = sokoworld::level::pack::parse_manifest(&manifest_str).unwrap();
+    manifest.sort();
+
+    let mut pack_files: Vec<String> = std::fs::read_dir("./assets/packs/")
+        .unwrap()
+        .map(|path| path.unwrap().file_name().into_string().unwrap())
+        .collect();
+    pack_files.sort();
+
+    assert_eq!(manifest, pack_files);
+}